@@ -242,7 +242,8 @@ export type Crop = { position: XY<number>; size: XY<number> }
 export type CurrentRecordingChanged = null
 export type CursorAnimationStyle = "regular" | "slow" | "fast"
 export type CursorConfiguration = { hideWhenIdle: boolean; size: number; type: CursorType; animationStyle: CursorAnimationStyle }
-export type CursorMeta = { imagePath: string; hotspot: XY<number> }
+export type CursorAnimationMeta = { framePaths: string[]; frameDurationMs: number }
+export type CursorMeta = { imagePath: string; hotspot: XY<number>; scale?: number; animation?: CursorAnimationMeta | null }
 export type CursorType = "pointer" | "circle"
 export type Cursors = { [key in string]: string } | { [key in string]: CursorMeta }
 export type Display = { path: string; fps?: number }
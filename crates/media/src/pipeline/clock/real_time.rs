@@ -83,6 +83,15 @@ impl<T: LocalTimestamp> RealTimeClock<T> {
         }
     }
 
+    /// When the clock was last started, which is what timestamps count from. Other
+    /// recorders can measure from this to stay in sync with the pipeline.
+    pub fn start_time(&self) -> Instant {
+        *self
+            .global_start_time
+            .read()
+            .expect("Pipeline clock has been poisoned!")
+    }
+
     fn set_running(&self, value: bool) {
         self.running.store(value, Ordering::Release);
     }
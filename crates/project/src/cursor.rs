@@ -14,18 +14,174 @@ pub struct CursorMoveEvent {
     pub unix_time_ms: f64,
     pub x: f64,
     pub y: f64,
+    /// Raw device pixel position the OS reported, before normalizing. Recordings made
+    /// before these were added read as 0.
+    #[serde(default)]
+    pub screen_x: i32,
+    #[serde(default)]
+    pub screen_y: i32,
+    /// Index of the recorded monitor the cursor was over, or `None` if it was outside
+    /// all of them and the position was clamped to the first one.
+    #[serde(default)]
+    pub monitor: Option<u32>,
+    /// The OS's id for `monitor` when the recorder was given one, which unlike the index
+    /// stays the same across segments even if displays were reordered in between.
+    /// Otherwise the same as `monitor`.
+    #[serde(default)]
+    pub monitor_id: Option<u32>,
+    /// Pointing device that was last used, so a pen can be told apart from a mouse. 0 is
+    /// the system pointer that devices are merged into where the platform can't tell
+    /// them apart, and what recordings made before this was added read as. Other ids are
+    /// only meaningful within a recording.
+    #[serde(default)]
+    pub device_id: u32,
+    /// Whether the OS was showing the cursor. Apps like fullscreen video players hide
+    /// it, and no cursor should be rendered until the next visible move.
+    #[serde(default = "visible_by_default")]
+    pub visible: bool,
+    /// Whether the cursor was outside every recorded monitor, such as on one that isn't
+    /// being recorded. `x` and `y` are then clamped to the first monitor's edge and
+    /// `monitor` is `None`, so no cursor should be rendered until the next move that's
+    /// back on screen.
+    #[serde(default)]
+    pub off_screen: bool,
+    /// Normalized units per second, measured from the previous sample.
+    #[serde(default)]
+    pub velocity_x: f64,
+    #[serde(default)]
+    pub velocity_y: f64,
+    /// Normalized units per second squared, measured from the previous sample.
+    #[serde(default)]
+    pub acceleration_x: f64,
+    #[serde(default)]
+    pub acceleration_y: f64,
+    /// Relative motion since the previous move, when the recorder was asked for it.
+    /// Unlike `screen_x` and `screen_y` this keeps counting while the cursor is pinned
+    /// in place, such as by a game that captured it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta: Option<MotionDelta>,
+}
+
+fn visible_by_default() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Type, Debug, PartialEq)]
+pub struct MotionDelta {
+    pub x: f64,
+    pub y: f64,
+    /// Whether this came from the platform's raw input, in device counts before pointer
+    /// acceleration. Otherwise it's the change in device pixel position, which stops at
+    /// screen edges and is zero while the cursor is pinned.
+    pub raw: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Type, Debug)]
 pub struct CursorClickEvent {
     pub active_modifiers: Vec<String>,
+    /// Raw button index, whose meaning differs by platform. Prefer `button`.
     pub cursor_num: u8,
+    /// `None` for recordings made before buttons were identified.
+    #[serde(default)]
+    pub button: Option<MouseButton>,
     pub cursor_id: String,
+    /// When the recorder saw the button change. Buttons are polled, so this can be up to
+    /// one polling interval after the actual press or release.
     pub process_time_ms: f64,
     pub unix_time_ms: f64,
     pub down: bool,
+    /// On a release, how long the button was held since its press, so the press doesn't
+    /// have to be looked up. `None` on presses, and on releases of buttons that were
+    /// already held when recording started.
+    #[serde(default)]
+    pub duration_ms: Option<f64>,
+    /// 2 for both halves of the second click in a double-click, 3 for a triple-click, etc.
+    #[serde(default = "single_click")]
+    pub click_count: u8,
+    /// On Windows and macOS, a press is placed where the system saw the button go down.
+    /// Releases, and presses on other platforms, are placed where the cursor was when the
+    /// change was polled, which trails a moving cursor's actual position.
+    pub x: f64,
+    pub y: f64,
+    /// See [`CursorMoveEvent::screen_x`].
+    #[serde(default)]
+    pub screen_x: i32,
+    #[serde(default)]
+    pub screen_y: i32,
+    /// Index of the recorded monitor the cursor was over, or `None` if it was outside
+    /// all of them and the position was clamped to the first one.
+    #[serde(default)]
+    pub monitor: Option<u32>,
+    /// See [`CursorMoveEvent::monitor_id`].
+    #[serde(default)]
+    pub monitor_id: Option<u32>,
+    /// See [`CursorMoveEvent::device_id`].
+    #[serde(default)]
+    pub device_id: u32,
+    /// Video frame the click is nearest to, once the moves have been resampled to the
+    /// video's frame rate. `process_time_ms` still has the exact time.
+    #[serde(default)]
+    pub frame: Option<u32>,
+}
+
+fn single_click() -> u8 {
+    1
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Type, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+    /// Any other button, by its raw index.
+    Other(u8),
+}
+
+/// Wheel movement since the previous sample, in lines (notches on a regular wheel).
+/// Positive `delta_y` scrolls up and positive `delta_x` scrolls right.
+#[derive(Serialize, Deserialize, Clone, Type, Debug)]
+pub struct CursorScrollEvent {
+    pub delta_x: f64,
+    pub delta_y: f64,
+    pub process_time_ms: f64,
+    pub unix_time_ms: f64,
     pub x: f64,
     pub y: f64,
+    /// Index of the recorded monitor the cursor was over, or `None` if it was outside
+    /// all of them and the position was clamped to the first one.
+    #[serde(default)]
+    pub monitor: Option<u32>,
+}
+
+/// A button held down while the cursor moved, synthesized from the matching clicks.
+#[derive(Serialize, Deserialize, Clone, Type, Debug)]
+pub struct CursorDragEvent {
+    pub cursor_num: u8,
+    pub start: XY<f64>,
+    pub end: XY<f64>,
+    pub start_process_time_ms: f64,
+    pub end_process_time_ms: f64,
+}
+
+/// A modifier key being pressed or released. Pressing either side of the keyboard counts
+/// as the same modifier, named as in `active_modifiers`.
+#[derive(Serialize, Deserialize, Clone, Type, Debug)]
+pub struct ModifierEvent {
+    pub modifier: String,
+    pub down: bool,
+    pub process_time_ms: f64,
+    pub unix_time_ms: f64,
+}
+
+/// The cursor turning into or out of the system's wait cursor, such as the spinner an
+/// app shows while loading.
+#[derive(Serialize, Deserialize, Clone, Type, Debug)]
+pub struct BusyStateEvent {
+    pub busy: bool,
+    pub process_time_ms: f64,
+    pub unix_time_ms: f64,
 }
 
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
@@ -42,6 +198,14 @@ pub struct CursorImage {
 pub struct CursorData {
     pub clicks: Vec<CursorClickEvent>,
     pub moves: Vec<CursorMoveEvent>,
+    #[serde(default)]
+    pub scrolls: Vec<CursorScrollEvent>,
+    #[serde(default)]
+    pub drags: Vec<CursorDragEvent>,
+    #[serde(default)]
+    pub modifier_events: Vec<ModifierEvent>,
+    #[serde(default)]
+    pub busy_events: Vec<BusyStateEvent>,
     pub cursor_images: CursorImages,
 }
 
@@ -56,6 +220,15 @@ impl CursorData {
 pub struct CursorEvents {
     pub clicks: Vec<CursorClickEvent>,
     pub moves: Vec<CursorMoveEvent>,
+    #[serde(default)]
+    pub scrolls: Vec<CursorScrollEvent>,
+    #[serde(default)]
+    pub drags: Vec<CursorDragEvent>,
+    /// Only recorded when opted into, see `CursorRecorderConfig::record_modifiers`.
+    #[serde(default)]
+    pub modifier_events: Vec<ModifierEvent>,
+    #[serde(default)]
+    pub busy_events: Vec<BusyStateEvent>,
 }
 
 impl CursorEvents {
@@ -70,6 +243,10 @@ impl From<CursorData> for CursorEvents {
         Self {
             clicks: value.clicks,
             moves: value.moves,
+            scrolls: value.scrolls,
+            drags: value.drags,
+            modifier_events: value.modifier_events,
+            busy_events: value.busy_events,
         }
     }
 }
@@ -177,6 +177,25 @@ pub struct CursorMeta {
     #[specta(type = String)]
     pub image_path: RelativePathBuf,
     pub hotspot: XY<f64>,
+    /// Image pixels per logical point, so high-DPI cursors can be drawn at their real size.
+    #[serde(default = "unscaled_cursor")]
+    pub scale: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub animation: Option<CursorAnimationMeta>,
+}
+
+/// Frames of an animated cursor. `image_path` is the first frame, and is what to show
+/// when the animation isn't played back.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorAnimationMeta {
+    #[specta(type = Vec<String>)]
+    pub frame_paths: Vec<RelativePathBuf>,
+    pub frame_duration_ms: f64,
+}
+
+fn unscaled_cursor() -> f64 {
+    1.0
 }
 
 impl MultipleSegments {
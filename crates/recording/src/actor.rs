@@ -21,14 +21,14 @@ use relative_path::{RelativePath, RelativePathBuf};
 use thiserror::Error;
 use tokio::sync::{oneshot, Mutex};
 use tracing::{
-    debug, info,
+    debug, error, info,
     instrument::{self, WithSubscriber},
     trace, Instrument,
 };
 use tracing_subscriber::{fmt::FormatFields, layer::SubscriberExt, Layer};
 
 use crate::{
-    cursor::{spawn_cursor_recorder, CursorActor, Cursors},
+    cursor::{project_cursor_meta, CursorActor, CursorRecorderConfig, Cursors},
     RecordingOptions,
 };
 
@@ -77,6 +77,10 @@ struct RecordingPipeline {
 struct CursorPipeline {
     output_path: PathBuf,
     actor: Option<CursorActor>,
+    // What the recorder started from, which the next segment continues from instead if
+    // this one's recorder fails
+    prev_cursors: Cursors,
+    next_cursor_id: u32,
 }
 
 #[derive(Clone)]
@@ -239,7 +243,9 @@ pub async fn spawn_recording_actor(
                                         _ = &mut pipeline_done_rx => {
                                             if let Some(cursor) = &mut pipeline.cursor {
                                                 if let Some(actor) = cursor.actor.take() {
-                                                    actor.stop().await;
+                                                    if let Err(e) = actor.stop().await {
+                                                        error!("{e}");
+                                                    }
                                                 }
                                             }
 
@@ -265,21 +271,37 @@ pub async fn spawn_recording_actor(
                                         let segment_stop_time = current_time_f64();
 
                                         let cursors = if let Some(cursor) = &mut pipeline.cursor {
-                                            if let Some(actor) = cursor.actor.take() {
-                                                let res = actor.stop().await;
-
-                                                std::fs::write(
-                                                    &cursor.output_path,
-                                                    serde_json::to_string_pretty(&CursorEvents {
-                                                        clicks: res.clicks,
-                                                        moves: res.moves,
-                                                    })?,
-                                                )?;
-
-                                                (res.cursors, res.next_cursor_id)
-                                            } else {
-                                                Default::default()
-                                            }
+                                            let res = match cursor.actor.take() {
+                                                Some(actor) => actor
+                                                    .stop()
+                                                    .await
+                                                    .map_err(|e| error!("{e}"))
+                                                    .ok(),
+                                                None => None,
+                                            };
+
+                                            // The segment's video is already finished, so it's
+                                            // kept without cursor events
+                                            let (events, cursors) = match res {
+                                                Some(res) => {
+                                                    let res = res.into_project_cursors();
+                                                    (res.events, (res.cursors, res.next_cursor_id))
+                                                }
+                                                None => (
+                                                    CursorEvents::default(),
+                                                    (
+                                                        std::mem::take(&mut cursor.prev_cursors),
+                                                        cursor.next_cursor_id,
+                                                    ),
+                                                ),
+                                            };
+
+                                            std::fs::write(
+                                                &cursor.output_path,
+                                                serde_json::to_string_pretty(&events)?,
+                                            )?;
+
+                                            cursors
                                         } else {
                                             Default::default()
                                         };
@@ -476,21 +498,7 @@ async fn stop_recording(
                         })
                         .collect()
                 },
-                cursors: cap_project::Cursors::Correct(
-                    cursors
-                        .into_values()
-                        .map(|cursor| {
-                            (
-                                cursor.id.to_string(),
-                                CursorMeta {
-                                    image_path: RelativePathBuf::from("content/cursors")
-                                        .join(&cursor.file_name),
-                                    hotspot: cursor.hotspot,
-                                },
-                            )
-                        })
-                        .collect(),
-                ),
+                cursors: project_cursor_meta(&cursors, RelativePath::new("content/cursors")),
             },
         },
     };
@@ -552,7 +560,7 @@ async fn create_segment_pipeline<TCaptureFormat: MakeCapturePipeline>(
     let dir = ensure_dir(&segments_dir.join(format!("segment-{index}")))?;
 
     let clock = RealTimeClock::<()>::new();
-    let mut pipeline_builder = Pipeline::builder(clock);
+    let mut pipeline_builder = Pipeline::builder(clock.clone());
 
     let display_output_path = dir.join("display.mp4");
 
@@ -626,24 +634,29 @@ async fn create_segment_pipeline<TCaptureFormat: MakeCapturePipeline>(
 
     let (mut pipeline, pipeline_done_rx) = pipeline_builder.build().await?;
 
+    pipeline.play().await?;
+
+    info!("pipeline playing");
+
+    // Started after the pipeline so both count from the clock's start time
     let cursor = FLAGS.record_mouse_state.then(|| {
-        let cursor = spawn_cursor_recorder(
-            screen_bounds,
-            cursors_dir.clone(),
-            prev_cursors,
-            next_cursors_id,
-        );
+        let actor = CursorRecorderConfig::new(screen_bounds, cursors_dir.clone())
+            .prev_cursors(prev_cursors.clone(), next_cursors_id)
+            // Roughly 15 minutes of continuous movement, so only long recordings spill
+            .spill_to_disk(dir.join("cursor.spill"), 100_000)
+            .start_time(clock.start_time())
+            .spawn()
+            .map_err(|e| error!("failed to start cursor recorder: {e}"))
+            .ok();
 
         CursorPipeline {
             output_path: dir.join("cursor.json"),
-            actor: Some(cursor),
+            actor,
+            prev_cursors,
+            next_cursor_id: next_cursors_id,
         }
     });
 
-    pipeline.play().await?;
-
-    info!("pipeline playing");
-
     Ok((
         RecordingPipeline {
             inner: pipeline,
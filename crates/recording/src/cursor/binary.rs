@@ -0,0 +1,655 @@
+use std::io::{Read, Write};
+
+use cap_project::{CursorClickEvent, CursorMoveEvent, MotionDelta, MouseButton};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use thiserror::Error;
+
+use super::CursorActorResponse;
+
+const MAGIC: &[u8; 4] = b"CAPC";
+const VERSION: u8 = 1;
+
+/// Times are stored in whole microseconds.
+const TIME_SCALE: f64 = 1000.0;
+/// Positions, velocities, accelerations and motion deltas are stored in millionths of a
+/// unit.
+const VALUE_SCALE: f64 = 1_000_000.0;
+
+// Flags of a move
+const VISIBLE: u8 = 1;
+const OFF_SCREEN: u8 = 2;
+const HAS_DELTA: u8 = 4;
+const RAW_DELTA: u8 = 8;
+
+#[derive(Error, Debug)]
+pub enum CursorDecodeError {
+    #[error("Not a binary cursor recording")]
+    BadMagic,
+    #[error("Unsupported binary cursor recording version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Binary cursor recording ends early")]
+    Truncated,
+    #[error("Binary cursor recording has invalid data: {0}")]
+    Invalid(&'static str),
+    #[error("Failed to decompress cursor recording")]
+    Decompress(#[source] std::io::Error),
+}
+
+impl CursorActorResponse {
+    /// Encodes `moves` and `clicks` far more compactly than JSON, for storage. Each
+    /// field is stored as the difference from the previous event's, so the small steps
+    /// between samples take a byte or two, and cursor ids and modifiers are only
+    /// written when they change.
+    ///
+    /// Times are rounded to the microsecond, and positions, velocities, accelerations and
+    /// motion deltas to a millionth, so [`CursorActorResponse::from_bytes`] gives back events that
+    /// match to that precision. Every other field of the response is left out.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.bytes.push(VERSION);
+
+        writer.uint(self.moves.len() as u64);
+        let mut prev = EventState::default();
+        for event in &self.moves {
+            writer.shared(
+                &mut prev,
+                &Shared {
+                    active_modifiers: &event.active_modifiers,
+                    cursor_id: &event.cursor_id,
+                    process_time_ms: event.process_time_ms,
+                    unix_time_ms: event.unix_time_ms,
+                    x: event.x,
+                    y: event.y,
+                    screen_x: event.screen_x,
+                    screen_y: event.screen_y,
+                    monitor: event.monitor,
+                    monitor_id: event.monitor_id,
+                    device_id: event.device_id,
+                },
+            );
+            let mut flags = 0;
+            if event.visible {
+                flags |= VISIBLE;
+            }
+            if event.off_screen {
+                flags |= OFF_SCREEN;
+            }
+            if let Some(delta) = event.delta {
+                flags |= HAS_DELTA;
+                if delta.raw {
+                    flags |= RAW_DELTA;
+                }
+            }
+            writer.bytes.push(flags);
+            for (value, prev) in [
+                (event.velocity_x, &mut prev.velocity_x),
+                (event.velocity_y, &mut prev.velocity_y),
+                (event.acceleration_x, &mut prev.acceleration_x),
+                (event.acceleration_y, &mut prev.acceleration_y),
+            ] {
+                writer.delta(quantize(value, VALUE_SCALE), prev);
+            }
+            // Already relative, so stored as is
+            if let Some(delta) = event.delta {
+                writer.int(quantize(delta.x, VALUE_SCALE));
+                writer.int(quantize(delta.y, VALUE_SCALE));
+            }
+        }
+
+        writer.uint(self.clicks.len() as u64);
+        let mut prev = EventState::default();
+        for event in &self.clicks {
+            writer.shared(
+                &mut prev,
+                &Shared {
+                    active_modifiers: &event.active_modifiers,
+                    cursor_id: &event.cursor_id,
+                    process_time_ms: event.process_time_ms,
+                    unix_time_ms: event.unix_time_ms,
+                    x: event.x,
+                    y: event.y,
+                    screen_x: event.screen_x,
+                    screen_y: event.screen_y,
+                    monitor: event.monitor,
+                    monitor_id: event.monitor_id,
+                    device_id: event.device_id,
+                },
+            );
+            writer.bytes.push(event.down as u8);
+            writer.bytes.push(event.cursor_num);
+            writer.bytes.push(event.click_count);
+            writer.button(event.button);
+            writer.optional(event.duration_ms.map(|ms| quantize(ms, TIME_SCALE) as u64));
+            writer.optional(event.frame.map(u64::from));
+        }
+
+        writer.bytes
+    }
+
+    /// Decodes what [`CursorActorResponse::to_bytes`] encoded, into a response with only
+    /// `moves` and `clicks` filled in.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CursorDecodeError> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(CursorDecodeError::BadMagic);
+        }
+        match reader.byte()? {
+            VERSION => {}
+            version => return Err(CursorDecodeError::UnsupportedVersion(version)),
+        }
+
+        let mut response = Self::default();
+
+        let count = reader.len()?;
+        let mut prev = EventState::default();
+        for _ in 0..count {
+            let shared = reader.shared(&mut prev)?;
+            let flags = reader.byte()?;
+            let mut values = [0.0; 4];
+            for (value, prev) in values.iter_mut().zip([
+                &mut prev.velocity_x,
+                &mut prev.velocity_y,
+                &mut prev.acceleration_x,
+                &mut prev.acceleration_y,
+            ]) {
+                *value = reader.delta(prev)? as f64 / VALUE_SCALE;
+            }
+            let [velocity_x, velocity_y, acceleration_x, acceleration_y] = values;
+            let delta = if flags & HAS_DELTA != 0 {
+                Some(MotionDelta {
+                    x: reader.int()? as f64 / VALUE_SCALE,
+                    y: reader.int()? as f64 / VALUE_SCALE,
+                    raw: flags & RAW_DELTA != 0,
+                })
+            } else {
+                None
+            };
+
+            response.moves.push(CursorMoveEvent {
+                active_modifiers: shared.active_modifiers,
+                cursor_id: shared.cursor_id,
+                process_time_ms: shared.process_time_ms,
+                unix_time_ms: shared.unix_time_ms,
+                x: shared.x,
+                y: shared.y,
+                screen_x: shared.screen_x,
+                screen_y: shared.screen_y,
+                monitor: shared.monitor,
+                monitor_id: shared.monitor_id,
+                device_id: shared.device_id,
+                visible: flags & VISIBLE != 0,
+                off_screen: flags & OFF_SCREEN != 0,
+                velocity_x,
+                velocity_y,
+                acceleration_x,
+                acceleration_y,
+                delta,
+            });
+        }
+
+        let count = reader.len()?;
+        let mut prev = EventState::default();
+        for _ in 0..count {
+            let shared = reader.shared(&mut prev)?;
+            let down = reader.byte()? != 0;
+            let cursor_num = reader.byte()?;
+            let click_count = reader.byte()?;
+            let button = reader.button()?;
+            let duration_ms = reader.optional()?.map(|us| us as f64 / TIME_SCALE);
+            let frame = reader.optional_u32("frame")?;
+
+            response.clicks.push(CursorClickEvent {
+                active_modifiers: shared.active_modifiers,
+                cursor_num,
+                button,
+                cursor_id: shared.cursor_id,
+                process_time_ms: shared.process_time_ms,
+                unix_time_ms: shared.unix_time_ms,
+                down,
+                duration_ms,
+                click_count,
+                x: shared.x,
+                y: shared.y,
+                screen_x: shared.screen_x,
+                screen_y: shared.screen_y,
+                monitor: shared.monitor,
+                monitor_id: shared.monitor_id,
+                device_id: shared.device_id,
+                frame,
+            });
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(CursorDecodeError::Invalid("trailing bytes"));
+        }
+
+        Ok(response)
+    }
+}
+
+impl CursorActorResponse {
+    /// [`CursorActorResponse::to_bytes`], zlib compressed for archiving. Consecutive
+    /// samples tend to differ in the same few ways, which the compression picks up on.
+    pub fn to_compressed_bytes(&self) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder
+            .write_all(&self.to_bytes())
+            .and_then(|()| encoder.finish())
+            .expect("writing to a Vec can't fail")
+    }
+
+    /// Decodes what [`CursorActorResponse::to_compressed_bytes`] encoded.
+    pub fn from_compressed_bytes(bytes: &[u8]) -> Result<Self, CursorDecodeError> {
+        let mut decompressed = Vec::new();
+        ZlibDecoder::new(bytes)
+            .read_to_end(&mut decompressed)
+            .map_err(CursorDecodeError::Decompress)?;
+        Self::from_bytes(&decompressed)
+    }
+}
+
+fn quantize(value: f64, scale: f64) -> i64 {
+    (value * scale).round() as i64
+}
+
+/// Fields moves and clicks have in common.
+struct Shared<M, C> {
+    active_modifiers: M,
+    cursor_id: C,
+    process_time_ms: f64,
+    unix_time_ms: f64,
+    x: f64,
+    y: f64,
+    screen_x: i32,
+    screen_y: i32,
+    monitor: Option<u32>,
+    monitor_id: Option<u32>,
+    device_id: u32,
+}
+
+/// The previous event's values, which the next one is stored relative to.
+#[derive(Default)]
+struct EventState {
+    active_modifiers: Vec<String>,
+    cursor_id: String,
+    process_time: i64,
+    // Relative to the process time, as both clocks advance together
+    unix_offset: i64,
+    x: i64,
+    y: i64,
+    screen_x: i64,
+    screen_y: i64,
+    velocity_x: i64,
+    velocity_y: i64,
+    acceleration_x: i64,
+    acceleration_y: i64,
+}
+
+const SAME_CURSOR: u8 = 1;
+const SAME_MODIFIERS: u8 = 2;
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn uint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    fn int(&mut self, value: i64) {
+        self.uint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn delta(&mut self, value: i64, prev: &mut i64) {
+        self.int(value.wrapping_sub(*prev));
+        *prev = value;
+    }
+
+    fn str(&mut self, value: &str) {
+        self.uint(value.len() as u64);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    /// 0 for `None`, otherwise one more than the value.
+    fn optional(&mut self, value: Option<u64>) {
+        self.uint(value.map_or(0, |value| value + 1));
+    }
+
+    fn button(&mut self, button: Option<MouseButton>) {
+        match button {
+            None => self.bytes.push(0),
+            Some(MouseButton::Left) => self.bytes.push(1),
+            Some(MouseButton::Right) => self.bytes.push(2),
+            Some(MouseButton::Middle) => self.bytes.push(3),
+            Some(MouseButton::Back) => self.bytes.push(4),
+            Some(MouseButton::Forward) => self.bytes.push(5),
+            Some(MouseButton::Other(index)) => self.bytes.extend_from_slice(&[6, index]),
+        }
+    }
+
+    fn shared(&mut self, prev: &mut EventState, event: &Shared<&[String], &str>) {
+        let same_cursor = event.cursor_id == prev.cursor_id;
+        let same_modifiers = event.active_modifiers == prev.active_modifiers;
+        let mut flags = 0;
+        if same_cursor {
+            flags |= SAME_CURSOR;
+        }
+        if same_modifiers {
+            flags |= SAME_MODIFIERS;
+        }
+        self.bytes.push(flags);
+        if !same_cursor {
+            self.str(event.cursor_id);
+            prev.cursor_id = event.cursor_id.to_string();
+        }
+        if !same_modifiers {
+            self.uint(event.active_modifiers.len() as u64);
+            for modifier in event.active_modifiers {
+                self.str(modifier);
+            }
+            prev.active_modifiers = event.active_modifiers.to_vec();
+        }
+
+        let process_time = quantize(event.process_time_ms, TIME_SCALE);
+        self.delta(process_time, &mut prev.process_time);
+        self.delta(
+            quantize(event.unix_time_ms, TIME_SCALE).wrapping_sub(process_time),
+            &mut prev.unix_offset,
+        );
+        self.delta(quantize(event.x, VALUE_SCALE), &mut prev.x);
+        self.delta(quantize(event.y, VALUE_SCALE), &mut prev.y);
+        self.delta(event.screen_x as i64, &mut prev.screen_x);
+        self.delta(event.screen_y as i64, &mut prev.screen_y);
+        self.optional(event.monitor.map(u64::from));
+        self.optional(event.monitor_id.map(u64::from));
+        self.uint(event.device_id as u64);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CursorDecodeError> {
+        if self.bytes.len() < len {
+            return Err(CursorDecodeError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, CursorDecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint(&mut self) -> Result<u64, CursorDecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(CursorDecodeError::Invalid("varint too long"))
+    }
+
+    fn int(&mut self) -> Result<i64, CursorDecodeError> {
+        let value = self.uint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    fn delta(&mut self, prev: &mut i64) -> Result<i64, CursorDecodeError> {
+        *prev = prev.wrapping_add(self.int()?);
+        Ok(*prev)
+    }
+
+    /// A count of items that each take at least a byte, so a corrupt count can't make
+    /// the decoder allocate more than the input could hold.
+    fn len(&mut self) -> Result<usize, CursorDecodeError> {
+        let len = self.uint()?;
+        if len > self.bytes.len() as u64 {
+            return Err(CursorDecodeError::Truncated);
+        }
+        Ok(len as usize)
+    }
+
+    fn str(&mut self) -> Result<String, CursorDecodeError> {
+        let len = self.len()?;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| CursorDecodeError::Invalid("string isn't UTF-8"))
+    }
+
+    fn optional(&mut self) -> Result<Option<u64>, CursorDecodeError> {
+        Ok(self.uint()?.checked_sub(1))
+    }
+
+    fn shared(
+        &mut self,
+        prev: &mut EventState,
+    ) -> Result<Shared<Vec<String>, String>, CursorDecodeError> {
+        let flags = self.byte()?;
+        if flags & SAME_CURSOR == 0 {
+            prev.cursor_id = self.str()?;
+        }
+        if flags & SAME_MODIFIERS == 0 {
+            let count = self.len()?;
+            prev.active_modifiers = (0..count).map(|_| self.str()).collect::<Result<_, _>>()?;
+        }
+
+        let process_time = self.delta(&mut prev.process_time)?;
+        let unix_time = self
+            .delta(&mut prev.unix_offset)?
+            .wrapping_add(process_time);
+        let x = self.delta(&mut prev.x)?;
+        let y = self.delta(&mut prev.y)?;
+        let screen_x = self.delta(&mut prev.screen_x)?;
+        let screen_y = self.delta(&mut prev.screen_y)?;
+        let monitor = self.optional_u32("monitor")?;
+        let monitor_id = self.optional_u32("monitor id")?;
+        let device_id =
+            u32::try_from(self.uint()?).map_err(|_| CursorDecodeError::Invalid("device id"))?;
+
+        Ok(Shared {
+            active_modifiers: prev.active_modifiers.clone(),
+            cursor_id: prev.cursor_id.clone(),
+            process_time_ms: process_time as f64 / TIME_SCALE,
+            unix_time_ms: unix_time as f64 / TIME_SCALE,
+            x: x as f64 / VALUE_SCALE,
+            y: y as f64 / VALUE_SCALE,
+            screen_x: i32::try_from(screen_x)
+                .map_err(|_| CursorDecodeError::Invalid("screen position"))?,
+            screen_y: i32::try_from(screen_y)
+                .map_err(|_| CursorDecodeError::Invalid("screen position"))?,
+            monitor,
+            monitor_id,
+            device_id,
+        })
+    }
+
+    fn optional_u32(&mut self, field: &'static str) -> Result<Option<u32>, CursorDecodeError> {
+        self.optional()?
+            .map(u32::try_from)
+            .transpose()
+            .map_err(|_| CursorDecodeError::Invalid(field))
+    }
+
+    fn button(&mut self) -> Result<Option<MouseButton>, CursorDecodeError> {
+        Ok(Some(match self.byte()? {
+            0 => return Ok(None),
+            1 => MouseButton::Left,
+            2 => MouseButton::Right,
+            3 => MouseButton::Middle,
+            4 => MouseButton::Back,
+            5 => MouseButton::Forward,
+            6 => MouseButton::Other(self.byte()?),
+            _ => return Err(CursorDecodeError::Invalid("button")),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn move_at(i: usize) -> CursorMoveEvent {
+        CursorMoveEvent {
+            active_modifiers: if i % 50 < 10 {
+                vec!["Shift".to_string()]
+            } else {
+                vec![]
+            },
+            cursor_id: (i / 100).to_string(),
+            process_time_ms: 12.5 + i as f64 * 10.0,
+            unix_time_ms: 1_700_000_000_000.0 + i as f64 * 10.0,
+            x: 0.25 + i as f64 * 0.0005,
+            y: 0.75 - i as f64 * 0.0002,
+            screen_x: 250 + i as i32,
+            screen_y: 750 - i as i32 / 2,
+            monitor: Some(0),
+            monitor_id: Some(7),
+            device_id: 0,
+            visible: !i.is_multiple_of(3),
+            off_screen: false,
+            velocity_x: 0.05,
+            velocity_y: -0.02,
+            acceleration_x: 0.0,
+            acceleration_y: 0.001,
+            delta: i.is_multiple_of(4).then(|| MotionDelta {
+                x: i as f64 * 0.5,
+                y: -1.0,
+                raw: i.is_multiple_of(8),
+            }),
+        }
+    }
+
+    fn response() -> CursorActorResponse {
+        CursorActorResponse {
+            moves: (0..1000).map(move_at).collect(),
+            clicks: vec![
+                CursorClickEvent {
+                    active_modifiers: vec!["Alt".to_string()],
+                    cursor_num: 1,
+                    button: Some(MouseButton::Left),
+                    cursor_id: "3".to_string(),
+                    process_time_ms: 40.125,
+                    unix_time_ms: 1_700_000_000_040.0,
+                    down: true,
+                    duration_ms: None,
+                    click_count: 2,
+                    x: 0.5,
+                    y: 0.5,
+                    screen_x: 500,
+                    screen_y: 500,
+                    monitor: None,
+                    monitor_id: None,
+                    device_id: 3,
+                    frame: Some(2),
+                },
+                CursorClickEvent {
+                    active_modifiers: vec![],
+                    cursor_num: 9,
+                    button: Some(MouseButton::Other(9)),
+                    cursor_id: "default".to_string(),
+                    process_time_ms: 90.0,
+                    unix_time_ms: 1_700_000_000_090.0,
+                    down: false,
+                    duration_ms: Some(49.875),
+                    click_count: 1,
+                    x: 0.5,
+                    y: 0.6,
+                    screen_x: 500,
+                    screen_y: 600,
+                    monitor: Some(1),
+                    monitor_id: Some(1),
+                    device_id: 3,
+                    frame: None,
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn round_trips_moves_and_clicks() {
+        let response = response();
+        let decoded = CursorActorResponse::from_bytes(&response.to_bytes()).unwrap();
+
+        // Debug output compares every field, which the events don't implement PartialEq for
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+        assert_eq!(decoded.moves.len(), response.moves.len());
+        for (decoded, original) in decoded.moves.iter().zip(&response.moves) {
+            assert!(close(decoded.process_time_ms, original.process_time_ms));
+            assert!(close(decoded.unix_time_ms, original.unix_time_ms));
+            assert!(close(decoded.x, original.x) && close(decoded.y, original.y));
+            assert!(close(decoded.velocity_y, original.velocity_y));
+            assert_eq!(decoded.active_modifiers, original.active_modifiers);
+            assert_eq!(decoded.cursor_id, original.cursor_id);
+            assert_eq!(
+                (decoded.screen_x, decoded.screen_y, decoded.visible),
+                (original.screen_x, original.screen_y, original.visible)
+            );
+            assert_eq!(decoded.monitor_id, original.monitor_id);
+            assert_eq!(decoded.delta, original.delta);
+        }
+        assert_eq!(
+            format!("{:?}", decoded.clicks),
+            format!("{:?}", response.clicks)
+        );
+    }
+
+    #[test]
+    fn smaller_than_json() {
+        let response = response();
+        let json = serde_json::to_vec(&response.moves).unwrap();
+        assert!(response.to_bytes().len() * 10 < json.len());
+    }
+
+    #[test]
+    fn round_trips_compressed() {
+        let response = CursorActorResponse {
+            moves: (0..100_000).map(move_at).collect(),
+            ..response()
+        };
+        let bytes = response.to_bytes();
+        let compressed = response.to_compressed_bytes();
+        assert!(compressed.len() < bytes.len());
+
+        let decoded = CursorActorResponse::from_compressed_bytes(&compressed).unwrap();
+        assert_eq!(decoded.moves.len(), 100_000);
+        assert_eq!(decoded.clicks.len(), 2);
+        // Decoded values are already rounded, so they encode to exactly the same bytes
+        assert!(decoded.to_bytes() == bytes);
+
+        assert!(matches!(
+            CursorActorResponse::from_compressed_bytes(&bytes),
+            Err(CursorDecodeError::Decompress(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let bytes = response().to_bytes();
+        assert!(matches!(
+            CursorActorResponse::from_bytes(b"{}"),
+            Err(CursorDecodeError::Truncated)
+        ));
+        assert!(matches!(
+            CursorActorResponse::from_bytes(b"JSON and more"),
+            Err(CursorDecodeError::BadMagic)
+        ));
+        assert!(matches!(
+            CursorActorResponse::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(CursorDecodeError::Truncated)
+        ));
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use image::{imageops::FilterType, GenericImageView};
+
+/// Merges cursor images that only differ in ways nobody would see, like antialiasing
+/// that changed a few pixels, which exact hashing would save as separate cursors.
+///
+/// Images are compared by a difference hash (dHash) of their shape, plus their average
+/// color, as dHash alone can't tell a black cursor from a white one of the same shape.
+/// Images of different sizes are never merged, since their hotspot and scale differ.
+pub struct SimilarCursors {
+    max_distance: u32,
+    known: Vec<(Fingerprint, u32)>,
+    // Exact image hashes already matched to a cursor, so repeats skip decoding
+    aliases: HashMap<u64, u32>,
+}
+
+/// Largest difference in each average color channel that still counts as the same
+/// cursor.
+const MAX_COLOR_DIFFERENCE: u8 = 8;
+
+#[derive(Clone, Copy)]
+struct Fingerprint {
+    dhash: u64,
+    dimensions: (u32, u32),
+    average: [u8; 4],
+}
+
+impl SimilarCursors {
+    /// `max_distance` is how many of the 64 dHash bits may differ.
+    pub fn new(max_distance: u32) -> Self {
+        Self {
+            max_distance,
+            known: vec![],
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Finds a cursor that looks the same as `image`, whose exact hash is `hash`.
+    pub fn find(&mut self, hash: u64, image: &[u8]) -> Option<u32> {
+        if let Some(id) = self.aliases.get(&hash) {
+            return Some(*id);
+        }
+
+        let fingerprint = Fingerprint::of(image)?;
+        let id = self
+            .known
+            .iter()
+            .find(|(known, _)| known.matches(&fingerprint, self.max_distance))
+            .map(|(_, id)| *id)?;
+
+        self.aliases.insert(hash, id);
+        Some(id)
+    }
+
+    /// Remembers `image` as what cursor `id` looks like.
+    pub fn insert(&mut self, hash: u64, image: &[u8], id: u32) {
+        if let Some(fingerprint) = Fingerprint::of(image) {
+            self.known.push((fingerprint, id));
+            self.aliases.insert(hash, id);
+        }
+    }
+}
+
+impl Fingerprint {
+    fn of(image: &[u8]) -> Option<Self> {
+        let image = image::load_from_memory(image).ok()?;
+        let dimensions = image.dimensions();
+
+        // Translucent pixels are blended onto mid-gray, so both black and white parts of
+        // a cursor stand out from its transparent surroundings
+        let gray = |pixel: &image::Rgba<u8>| {
+            let [r, g, b, a] = pixel.0.map(u32::from);
+            let luma = (r * 299 + g * 587 + b * 114) / 1000;
+            (luma * a + 128 * (255 - a)) / 255
+        };
+
+        let small = image::imageops::resize(&image.to_rgba8(), 9, 8, FilterType::Triangle);
+        let mut dhash = 0;
+        for y in 0..8 {
+            for x in 0..8 {
+                let brighter = gray(small.get_pixel(x, y)) > gray(small.get_pixel(x + 1, y));
+                dhash = dhash << 1 | brighter as u64;
+            }
+        }
+
+        let average = image::imageops::resize(&image.to_rgba8(), 1, 1, FilterType::Triangle)
+            .get_pixel(0, 0)
+            .0;
+
+        Some(Self {
+            dhash,
+            dimensions,
+            average,
+        })
+    }
+
+    fn matches(&self, other: &Self, max_distance: u32) -> bool {
+        self.dimensions == other.dimensions
+            && (self.dhash ^ other.dhash).count_ones() <= max_distance
+            && self
+                .average
+                .iter()
+                .zip(other.average)
+                .all(|(a, b)| a.abs_diff(b) <= MAX_COLOR_DIFFERENCE)
+    }
+}
@@ -0,0 +1,110 @@
+//! Decoding, encoding and saving cursor images, only built with the `cursor-images`
+//! feature.
+
+use std::path::Path;
+
+use cap_project::XY;
+use image::GenericImageView;
+
+pub use image::ImageError;
+
+use super::{CursorAnimation, CursorData};
+
+/// Reads the size from the image's header, without decoding it.
+pub fn image_dimensions(image: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(std::io::Cursor::new(image))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+pub fn save_cursor_image(image: &[u8], path: &Path) -> Result<(u32, u32), ImageError> {
+    let image = image::load_from_memory(image)?;
+    let dimensions = image.dimensions();
+
+    // Convert to RGBA, in whichever format the extension picks
+    image.into_rgba8().save(path)?;
+
+    Ok(dimensions)
+}
+
+/// Saves the cursor image, then any further frames of an animated cursor next to it.
+pub fn save_cursor_images(
+    data: &CursorData,
+    path: &Path,
+    animation: Option<&CursorAnimation>,
+    dir: &Path,
+) -> Result<(u32, u32), ImageError> {
+    let dimensions = save_cursor_image(&data.image, path)?;
+
+    if let Some(animation) = animation {
+        for (frame, file_name) in data.frames.iter().zip(&animation.frame_files[1..]) {
+            save_cursor_image(frame, &dir.join(file_name))?;
+        }
+    }
+
+    Ok(dimensions)
+}
+
+/// Every platform hands cursor images to the recorder as PNG, so identical cursors
+/// hash identically and saved files match their `.png` extension.
+pub fn encode_png(image: &image::RgbaImage) -> Option<Vec<u8>> {
+    let mut png_data = Vec::new();
+    image
+        .write_to(
+            &mut std::io::Cursor::new(&mut png_data),
+            image::ImageFormat::Png,
+        )
+        .ok()?;
+    Some(png_data)
+}
+
+/// Scales the cursor to fit `width` by `height` and centers it on a transparent canvas of
+/// that size. Images that can't be decoded are left as they are.
+pub fn normalize_cursor_size(data: &mut CursorData, (width, height): (u32, u32)) {
+    let Ok(image) = image::load_from_memory(&data.image) else {
+        return;
+    };
+    let (image_width, image_height) = image.dimensions();
+    if (image_width, image_height) == (width, height) {
+        return;
+    }
+
+    let factor = f64::min(
+        width as f64 / image_width as f64,
+        height as f64 / image_height as f64,
+    );
+    let fit = XY::new(
+        ((image_width as f64 * factor).round() as u32).clamp(1, width),
+        ((image_height as f64 * factor).round() as u32).clamp(1, height),
+    );
+    let offset = XY::new((width - fit.x) / 2, (height - fit.y) / 2);
+
+    let fit_image = |image: &image::DynamicImage| {
+        let resized = image::imageops::resize(
+            &image.to_rgba8(),
+            fit.x,
+            fit.y,
+            image::imageops::FilterType::Lanczos3,
+        );
+        let mut canvas = image::RgbaImage::new(width, height);
+        image::imageops::overlay(&mut canvas, &resized, offset.x as i64, offset.y as i64);
+        encode_png(&canvas)
+    };
+
+    let Some(png) = fit_image(&image) else {
+        return;
+    };
+    data.image = png;
+    data.frames = data
+        .frames
+        .iter()
+        .filter_map(|frame| fit_image(&image::load_from_memory(frame).ok()?))
+        .collect();
+    data.hotspot = XY::new(
+        (offset.x as f64 + data.hotspot.x * fit.x as f64) / width as f64,
+        (offset.y as f64 + data.hotspot.y * fit.y as f64) / height as f64,
+    );
+    data.scale *= fit.x as f64 / image_width as f64;
+}
@@ -0,0 +1,134 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use cap_project::CursorEvents;
+use tracing::error;
+
+use super::CursorEvent;
+
+/// Appends events to a sidecar file while recording, so a session that never reaches
+/// [`super::CursorActor::stop`], eg. because the app crashed, can still be recovered
+/// with [`recover_journal`] up to the last flush.
+///
+/// The file is JSON lines, each a [`CursorEvents`] chunk holding the events recorded
+/// since the previous line, so appending every chunk in order gives all events up to
+/// the last flush. A crash mid-write can cut the last line short.
+pub struct EventJournal {
+    path: PathBuf,
+    flush_interval: Duration,
+    file: Option<BufWriter<File>>,
+    pending: CursorEvents,
+    last_flush: Instant,
+    failed: bool,
+}
+
+impl EventJournal {
+    pub fn new(path: PathBuf, flush_interval: Duration) -> Self {
+        Self {
+            path,
+            flush_interval,
+            file: None,
+            pending: CursorEvents::default(),
+            last_flush: Instant::now(),
+            failed: false,
+        }
+    }
+
+    pub fn push(&mut self, event: &CursorEvent) {
+        if self.failed {
+            return;
+        }
+
+        match event.clone() {
+            CursorEvent::Move(event) => self.pending.moves.push(event),
+            CursorEvent::Click(event) => self.pending.clicks.push(event),
+            CursorEvent::Scroll(event) => self.pending.scrolls.push(event),
+            CursorEvent::Drag(event) => self.pending.drags.push(event),
+            CursorEvent::Modifier(event) => self.pending.modifier_events.push(event),
+            CursorEvent::Busy(event) => self.pending.busy_events.push(event),
+        }
+    }
+
+    pub fn flush_if_due(&mut self) {
+        if self.last_flush.elapsed() >= self.flush_interval {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+
+        let pending = &self.pending;
+        let buffered = pending.moves.len()
+            + pending.clicks.len()
+            + pending.scrolls.len()
+            + pending.drags.len()
+            + pending.modifier_events.len()
+            + pending.busy_events.len();
+        if self.failed || buffered == 0 {
+            return;
+        }
+
+        let chunk = std::mem::take(&mut self.pending);
+        if let Err(e) = self.write_chunk(&chunk) {
+            // The response still has every event, only crash recovery is lost
+            error!("Failed to write cursor event journal {:?}: {e}", self.path);
+            self.failed = true;
+        }
+    }
+
+    fn write_chunk(&mut self, chunk: &CursorEvents) -> std::io::Result<()> {
+        let line = serde_json::to_string(chunk)?;
+
+        let file = match &mut self.file {
+            Some(file) => file,
+            file => file.insert(BufWriter::new(File::create(&self.path)?)),
+        };
+        writeln!(file, "{line}")?;
+        file.flush()
+    }
+
+    /// Writes out the events that are still pending and leaves the journal in place, for
+    /// when the response won't have everything it held.
+    pub fn keep(mut self) {
+        self.flush();
+    }
+
+    /// Deletes the journal once recording stopped cleanly, as the response has
+    /// everything it held.
+    pub fn finish(self) {
+        let Some(file) = self.file else {
+            return;
+        };
+        drop(file);
+
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            error!("Failed to remove cursor event journal {:?}: {e}", self.path);
+        }
+    }
+}
+
+/// Reads back the events a crashed recording journaled with
+/// [`super::CursorRecorderConfig::journal`]. A last line that was cut short is
+/// skipped, so this returns every event up to the last complete flush.
+pub fn recover_journal(path: &Path) -> std::io::Result<CursorEvents> {
+    let mut events = CursorEvents::default();
+
+    for line in BufReader::new(File::open(path)?).lines() {
+        let Ok(chunk) = serde_json::from_str::<CursorEvents>(&line?) else {
+            break;
+        };
+        events.moves.extend(chunk.moves);
+        events.clicks.extend(chunk.clicks);
+        events.scrolls.extend(chunk.scrolls);
+        events.drags.extend(chunk.drags);
+        events.modifier_events.extend(chunk.modifier_events);
+        events.busy_events.extend(chunk.busy_events);
+    }
+
+    Ok(events)
+}
@@ -0,0 +1,460 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::warn;
+
+use super::{
+    accumulate_raw_motion, accumulate_scroll, enable_raw_motion, set_pointer_device,
+    set_press_position, CursorRecordError,
+};
+
+#[cfg(feature = "cursor-images")]
+use std::sync::{Mutex, PoisonError};
+
+#[cfg(feature = "cursor-images")]
+use cap_project::XY;
+
+#[cfg(feature = "cursor-images")]
+use super::{images::encode_png, CursorData, CursorKind};
+
+/// Retained reference to an `NSCursor`. Holding on to it keeps the object alive, so
+/// pointer equality can't be fooled by a new cursor reusing a freed address.
+#[cfg(feature = "cursor-images")]
+pub struct CursorHandle(cocoa::base::id);
+
+/// Handles only serve to skip capturing unchanged images, which are never captured
+/// without the `cursor-images` feature.
+#[cfg(not(feature = "cursor-images"))]
+#[derive(PartialEq)]
+pub enum CursorHandle {}
+
+// NSCursor is immutable and retain/release are thread safe
+#[cfg(feature = "cursor-images")]
+unsafe impl Send for CursorHandle {}
+
+#[cfg(feature = "cursor-images")]
+impl PartialEq for CursorHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+#[cfg(feature = "cursor-images")]
+impl Drop for CursorHandle {
+    fn drop(&mut self) {
+        use objc::*;
+
+        unsafe {
+            let _: () = msg_send![self.0, release];
+        }
+    }
+}
+
+/// The cursor last handed out and the cursor seed at the time, see
+/// [`cursor_possibly_stale`].
+#[cfg(feature = "cursor-images")]
+static LAST_CURSOR: Mutex<(usize, i32)> = Mutex::new((0, 0));
+static CURSOR_STALE: AtomicBool = AtomicBool::new(false);
+
+#[cfg(not(feature = "cursor-images"))]
+pub fn current_cursor_handle() -> Option<CursorHandle> {
+    None
+}
+
+#[cfg(feature = "cursor-images")]
+pub fn current_cursor_handle() -> Option<CursorHandle> {
+    use cocoa::base::{id, nil};
+    use objc::rc::autoreleasepool;
+    use objc::runtime::Class;
+    use objc::*;
+
+    autoreleasepool(|| unsafe {
+        let current_cursor: id = msg_send![Class::get("NSCursor")?, currentSystemCursor];
+        if current_cursor == nil {
+            return None;
+        }
+        track_staleness(current_cursor as usize, CGSCurrentCursorSeed());
+
+        let current_cursor: id = msg_send![current_cursor, retain];
+        Some(CursorHandle(current_cursor))
+    })
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGCursorIsVisible() -> i32;
+    fn CGPreflightListenEventAccess() -> bool;
+    // Private but long-standing, bumped whenever any process sets the cursor
+    #[cfg(feature = "cursor-images")]
+    fn CGSCurrentCursorSeed() -> i32;
+}
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+}
+
+/// Without Accessibility, positions stop updating while other apps are focused, and
+/// without Input Monitoring the event tap never sees scrolls or tablets. Neither check
+/// prompts the user.
+pub fn input_access() -> Result<(), CursorRecordError> {
+    if !unsafe { AXIsProcessTrusted() } {
+        return Err(CursorRecordError::AccessibilityDenied);
+    }
+
+    if !unsafe { CGPreflightListenEventAccess() } {
+        return Err(CursorRecordError::InputMonitoringDenied);
+    }
+
+    Ok(())
+}
+
+pub fn cursor_visible() -> bool {
+    unsafe { CGCursorIsVisible() != 0 }
+}
+
+/// `currentSystemCursor` is only refreshed as AppKit processes events, so while other
+/// apps change the cursor it can keep returning the one this process saw last. There's
+/// no public API for the cursor that's actually on screen, so this can only flag when
+/// the seed moved on without `currentSystemCursor` following. Private APIs for reading
+/// the hardware cursor, like `CGSGetGlobalCursorData`, aren't used as they can't be
+/// relied on from one macOS release to the next.
+#[cfg(feature = "cursor-images")]
+fn track_staleness(cursor: usize, seed: i32) {
+    let mut last = LAST_CURSOR.lock().unwrap_or_else(PoisonError::into_inner);
+    let (last_cursor, last_seed) = *last;
+    if cursor != last_cursor {
+        CURSOR_STALE.store(false, Ordering::Relaxed);
+    } else if seed != last_seed {
+        CURSOR_STALE.store(true, Ordering::Relaxed);
+    }
+    *last = (cursor, seed);
+}
+
+pub fn cursor_possibly_stale() -> bool {
+    CURSOR_STALE.load(Ordering::Relaxed)
+}
+
+/// Mouse positions are in points, like cursor sizes.
+pub fn position_scale() -> f64 {
+    1.0
+}
+
+#[cfg(feature = "cursor-images")]
+pub fn get_cursor_image_data() -> Option<CursorData> {
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSPoint, NSSize, NSUInteger};
+    use objc::rc::autoreleasepool;
+    use objc::runtime::Class;
+    use objc::*;
+
+    autoreleasepool(|| {
+        let nscursor_class = match Class::get("NSCursor") {
+            Some(cls) => cls,
+            None => return None,
+        };
+
+        unsafe {
+            // Get the current system cursor
+            let current_cursor: id = msg_send![nscursor_class, currentSystemCursor];
+            if current_cursor == nil {
+                return None;
+            }
+
+            // Get the image of the cursor
+            let cursor_image: id = msg_send![current_cursor, image];
+            if cursor_image == nil {
+                return None;
+            }
+
+            let cursor_size: NSSize = msg_send![cursor_image, size];
+            let cursor_hotspot: NSPoint = msg_send![current_cursor, hotSpot];
+
+            // Get the TIFF representation of the image
+            let image_data: id = msg_send![cursor_image, TIFFRepresentation];
+            if image_data == nil {
+                return None;
+            }
+
+            // Get the length of the data
+            let length: NSUInteger = msg_send![image_data, length];
+
+            // Get the bytes of the data
+            let bytes: *const u8 = msg_send![image_data, bytes];
+
+            let slice = std::slice::from_raw_parts(bytes, length as usize);
+
+            // The TIFF holds the cursor at the display's backing scale, while its size
+            // is in points
+            let (image, pixel_width) = tiff_to_png(slice)?;
+            let (system_name, kind) = match standard_cursor(current_cursor) {
+                Some((name, kind)) => (Some(name), kind),
+                None => (None, CursorKind::Custom),
+            };
+
+            Some(CursorData {
+                image,
+                hotspot: normalized_hotspot(
+                    XY::new(cursor_hotspot.x, cursor_hotspot.y),
+                    XY::new(cursor_size.width, cursor_size.height),
+                ),
+                kind,
+                system_name,
+                scale: pixel_width as f64 / cursor_size.width,
+                frames: vec![],
+                frame_duration_ms: 0.0,
+            })
+        }
+    })
+}
+
+/// AppKit's standard cursors, by the `NSCursor` class method that returns each. Cursors
+/// with no matching [`CursorKind`] are still named.
+///
+/// `busyButClickableCursor`, the arrow with a spinner, is private, so it's only matched
+/// on releases that still have it. The spinning wait cursor over an unresponsive app is
+/// drawn by the window server rather than set through `NSCursor`, so it may not be seen.
+#[cfg(feature = "cursor-images")]
+const STANDARD_CURSORS: &[(&str, CursorKind)] = &[
+    ("arrowCursor", CursorKind::Arrow),
+    ("IBeamCursor", CursorKind::IBeam),
+    ("IBeamCursorForVerticalLayout", CursorKind::IBeam),
+    ("pointingHandCursor", CursorKind::PointingHand),
+    ("openHandCursor", CursorKind::OpenHand),
+    ("closedHandCursor", CursorKind::ClosedHand),
+    ("crosshairCursor", CursorKind::Crosshair),
+    ("operationNotAllowedCursor", CursorKind::NotAllowed),
+    ("resizeLeftRightCursor", CursorKind::ResizeLeftRight),
+    ("resizeLeftCursor", CursorKind::ResizeLeftRight),
+    ("resizeRightCursor", CursorKind::ResizeLeftRight),
+    ("resizeUpDownCursor", CursorKind::ResizeUpDown),
+    ("resizeUpCursor", CursorKind::ResizeUpDown),
+    ("resizeDownCursor", CursorKind::ResizeUpDown),
+    ("dragLinkCursor", CursorKind::Custom),
+    ("dragCopyCursor", CursorKind::Custom),
+    ("contextualMenuCursor", CursorKind::Custom),
+    ("disappearingItemCursor", CursorKind::Custom),
+    ("busyButClickableCursor", CursorKind::Wait),
+];
+
+/// Matches the cursor against [`STANDARD_CURSORS`], returning its name, which is the
+/// class method's without the `Cursor` suffix, eg. `resizeLeftRight`, and its kind.
+#[cfg(feature = "cursor-images")]
+unsafe fn standard_cursor(cursor: cocoa::base::id) -> Option<(String, CursorKind)> {
+    use cocoa::base::id;
+    use objc::runtime::{Sel, YES};
+    use objc::*;
+
+    STANDARD_CURSORS
+        .iter()
+        .find(|(method, _)| {
+            let selector = Sel::register(method);
+            let responds: objc::runtime::BOOL =
+                msg_send![class!(NSCursor), respondsToSelector: selector];
+            if responds != YES {
+                return false;
+            }
+
+            let standard: id = msg_send![class!(NSCursor), performSelector: selector];
+            let equal: objc::runtime::BOOL = msg_send![cursor, isEqual: standard];
+            equal == YES
+        })
+        .map(|(method, kind)| (method.trim_end_matches("Cursor").to_string(), *kind))
+}
+
+/// Re-encodes as PNG so cursor hashes and saved files match the other platforms. Also
+/// returns the width of the image in pixels.
+#[cfg(feature = "cursor-images")]
+fn tiff_to_png(tiff: &[u8]) -> Option<(Vec<u8>, u32)> {
+    let image = image::load_from_memory_with_format(tiff, image::ImageFormat::Tiff).ok()?;
+    Some((encode_png(&image.to_rgba8())?, image.width()))
+}
+
+/// AppKit reports both the hotspot and the image's size in points, so dividing one by
+/// the other already gives the hotspot's place in the image at any backing scale, and
+/// the TIFF's pixel size doesn't come into it. Hotspots AppKit places past the image's
+/// edge are pulled back onto it.
+#[cfg(feature = "cursor-images")]
+fn normalized_hotspot(hotspot: XY<f64>, size: XY<f64>) -> XY<f64> {
+    let axis = |hotspot: f64, size: f64| {
+        if size <= 0.0 {
+            return 0.0;
+        }
+
+        (hotspot / size).clamp(0.0, 1.0)
+    };
+
+    XY::new(axis(hotspot.x, size.x), axis(hotspot.y, size.y))
+}
+
+pub fn spawn_input_listener() {
+    use core_foundation::runloop::{kCFRunLoopCommonModes, CFRunLoop};
+    use core_graphics::event::{
+        CGEventTap, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement, CGEventType,
+        EventField,
+    };
+
+    std::thread::spawn(|| {
+        let tap = CGEventTap::new(
+            CGEventTapLocation::Session,
+            CGEventTapPlacement::TailAppendEventTap,
+            CGEventTapOptions::ListenOnly,
+            vec![
+                CGEventType::ScrollWheel,
+                CGEventType::MouseMoved,
+                CGEventType::LeftMouseDown,
+                CGEventType::LeftMouseDragged,
+                CGEventType::RightMouseDown,
+                CGEventType::RightMouseDragged,
+                CGEventType::OtherMouseDown,
+                CGEventType::OtherMouseDragged,
+                CGEventType::TabletPointer,
+            ],
+            |_, event_type, event| {
+                match event_type {
+                    // Fixed point deltas are in lines but keep the fractional part trackpads
+                    // produce. Axis 2 is positive when scrolling left.
+                    CGEventType::ScrollWheel => accumulate_scroll(
+                        -event.get_double_value_field(
+                            EventField::SCROLL_WHEEL_EVENT_FIXED_POINT_DELTA_AXIS_2,
+                        ),
+                        event.get_double_value_field(
+                            EventField::SCROLL_WHEEL_EVENT_FIXED_POINT_DELTA_AXIS_1,
+                        ),
+                    ),
+                    CGEventType::LeftMouseDown
+                    | CGEventType::RightMouseDown
+                    | CGEventType::OtherMouseDown => {
+                        set_pointer_device(pointer_device(event_type, event));
+
+                        // Button numbers start at 0 for the left button, while the
+                        // recorder counts from 1 like device_query
+                        let button =
+                            event.get_integer_value_field(EventField::MOUSE_EVENT_BUTTON_NUMBER);
+                        let location = event.location();
+                        set_press_position(
+                            button as u8 + 1,
+                            (location.x as i32, location.y as i32),
+                        );
+                    }
+                    CGEventType::MouseMoved
+                    | CGEventType::LeftMouseDragged
+                    | CGEventType::RightMouseDragged
+                    | CGEventType::OtherMouseDragged => {
+                        set_pointer_device(pointer_device(event_type, event));
+
+                        // Unlike the location, these keep counting when the cursor is held
+                        // in place or stopped by a screen edge
+                        accumulate_raw_motion(
+                            event.get_integer_value_field(EventField::MOUSE_EVENT_DELTA_X) as f64,
+                            event.get_integer_value_field(EventField::MOUSE_EVENT_DELTA_Y) as f64,
+                        );
+                    }
+                    _ => set_pointer_device(pointer_device(event_type, event)),
+                }
+                None
+            },
+        );
+
+        let Ok(tap) = tap else {
+            warn!("Failed to create input event tap, scrolls and pointer devices will not be recorded");
+            return;
+        };
+
+        let Ok(source) = tap.mach_port.create_runloop_source(0) else {
+            warn!("Failed to create input event tap source, scrolls and pointer devices will not be recorded");
+            return;
+        };
+
+        unsafe {
+            CFRunLoop::get_current().add_source(&source, kCFRunLoopCommonModes);
+        }
+        tap.enable();
+        enable_raw_motion();
+        CFRunLoop::run_current();
+    });
+}
+
+/// Mice and trackpads all arrive as the system pointer, but tablets tag their events
+/// with the id of the device that sent them.
+fn pointer_device(
+    event_type: core_graphics::event::CGEventType,
+    event: &core_graphics::event::CGEvent,
+) -> u32 {
+    use core_graphics::event::{CGEventType, EventField};
+
+    // kCGEventMouseSubtypeTabletPoint
+    const TABLET_POINT: i64 = 1;
+
+    let is_tablet = matches!(event_type, CGEventType::TabletPointer)
+        || event.get_integer_value_field(EventField::MOUSE_EVENT_SUB_TYPE) == TABLET_POINT;
+    if !is_tablet {
+        return 0;
+    }
+
+    // Offset so a tablet never reads as the system pointer
+    (event.get_integer_value_field(EventField::TABLET_EVENT_DEVICE_ID) as u32).saturating_add(1)
+}
+
+#[cfg(all(test, feature = "cursor-images"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tiff_becomes_png() {
+        let image = image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 0, 0, 128]));
+
+        let mut tiff = Vec::new();
+        image
+            .write_to(
+                &mut std::io::Cursor::new(&mut tiff),
+                image::ImageFormat::Tiff,
+            )
+            .unwrap();
+
+        let (png, width) = tiff_to_png(&tiff).unwrap();
+        assert_eq!(width, 4);
+        assert_eq!(image::guess_format(&png).unwrap(), image::ImageFormat::Png);
+        assert_eq!(image::load_from_memory(&png).unwrap().into_rgba8(), image);
+    }
+
+    #[test]
+    fn normalizes_hotspot_in_points() {
+        let size = XY::new(32.0, 32.0);
+        assert_eq!(
+            normalized_hotspot(XY::new(16.0, 16.0), size),
+            XY::new(0.5, 0.5)
+        );
+        assert_eq!(
+            normalized_hotspot(XY::new(4.0, 28.0), size),
+            XY::new(0.125, 0.875)
+        );
+
+        // Past the edge, and a cursor without a size
+        assert_eq!(
+            normalized_hotspot(XY::new(40.0, -2.0), size),
+            XY::new(1.0, 0.0)
+        );
+        assert_eq!(
+            normalized_hotspot(XY::new(16.0, 16.0), XY::new(0.0, 0.0)),
+            XY::new(0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn names_standard_cursors() {
+        use cocoa::base::id;
+        use objc::*;
+
+        let arrow: id = unsafe { msg_send![class!(NSCursor), arrowCursor] };
+        assert_eq!(
+            unsafe { standard_cursor(arrow) },
+            Some(("arrow".to_string(), CursorKind::Arrow))
+        );
+
+        let resize: id = unsafe { msg_send![class!(NSCursor), resizeLeftRightCursor] };
+        assert_eq!(
+            unsafe { standard_cursor(resize) }.map(|(name, _)| name),
+            Some("resizeLeftRight".to_string())
+        );
+    }
+}
@@ -0,0 +1,6464 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU32},
+        Arc, Mutex, MutexGuard, Once, OnceLock, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use cap_media::platform::Bounds;
+use cap_project::{
+    BusyStateEvent, CursorAnimationMeta, CursorClickEvent, CursorDragEvent, CursorEvents,
+    CursorMeta, CursorMoveEvent, CursorScrollEvent, ModifierEvent, MotionDelta, MouseButton, XY,
+};
+use cap_utils::spawn_actor;
+use device_query::{DeviceQuery, DeviceState, Keycode, MouseState};
+use relative_path::RelativePath;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::{
+    sync::{mpsc, oneshot, watch},
+    task::JoinError,
+};
+use tracing::{debug, error, info, warn};
+
+mod binary;
+#[cfg(feature = "cursor-images")]
+mod dedup;
+mod journal;
+mod saver;
+mod sidecar;
+mod spill;
+
+#[cfg(feature = "cursor-images")]
+#[path = "images.rs"]
+mod images;
+
+#[cfg(not(feature = "cursor-images"))]
+#[path = "no_images.rs"]
+mod images;
+
+#[cfg(target_os = "macos")]
+#[path = "macos.rs"]
+mod platform_impl;
+
+#[cfg(windows)]
+#[path = "win.rs"]
+mod platform_impl;
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+#[path = "x11.rs"]
+mod x11_backend;
+
+#[cfg(all(target_os = "linux", feature = "x11", not(feature = "wayland")))]
+use x11_backend as platform_impl;
+
+// Falls back to the X11 backend at runtime outside of Wayland sessions
+#[cfg(all(target_os = "linux", feature = "wayland"))]
+#[path = "wayland.rs"]
+mod platform_impl;
+
+#[cfg(not(any(
+    target_os = "macos",
+    windows,
+    all(target_os = "linux", any(feature = "x11", feature = "wayland"))
+)))]
+#[path = "unsupported.rs"]
+mod platform_impl;
+
+#[cfg(feature = "cursor-images")]
+use dedup::SimilarCursors;
+#[cfg(not(feature = "cursor-images"))]
+use images::SimilarCursors;
+use images::{image_dimensions, normalize_cursor_size, save_cursor_images, ImageError};
+use journal::EventJournal;
+use saver::{CursorSave, CursorSaver, SaveOptions};
+use sidecar::write_cursor_sidecar;
+use spill::EventSpill;
+
+pub use binary::CursorDecodeError;
+pub use journal::recover_journal;
+pub use sidecar::{load_cursors_from_dir, CURSOR_SIDECAR_FILE};
+
+#[cfg(feature = "cursor-images")]
+use platform_impl::get_cursor_image_data;
+use platform_impl::{
+    current_cursor_handle, cursor_possibly_stale, cursor_visible, input_access, position_scale,
+    spawn_input_listener, CursorHandle,
+};
+
+/// Sampling cadence used when no explicit interval is configured.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Moves closer than this many pixels to the last recorded one are coalesced. Any
+/// change in position is at least a pixel, so the default keeps every move.
+pub const DEFAULT_MOVE_THRESHOLD: f64 = 1.0;
+
+/// Coalescing never spans more than this, so slow drags still get regular samples.
+pub const DEFAULT_COALESCE_WINDOW: Duration = Duration::from_millis(100);
+
+/// Longest gap between releasing a button and pressing it again that still counts as
+/// a double-click.
+pub const DEFAULT_DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How far, in pixels, the cursor may drift between the clicks of a double-click.
+pub const DEFAULT_DOUBLE_CLICK_RADIUS: f64 = 4.0;
+
+/// Far more distinct cursors than any normal recording sees, see
+/// [`CursorRecorderConfig::max_cursors`].
+pub const DEFAULT_MAX_CURSORS: usize = 1000;
+
+/// A tick that takes this many poll intervals to sample counts as an overrun.
+const OVERRUN_FACTOR: u32 = 2;
+
+/// Overruns are logged at most this often, as they tend to come in bursts.
+const OVERRUN_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Clicking and scrolling this many times without the position changing at all for
+/// [`STUCK_INPUT_AFTER`] makes input look stuck, see
+/// [`CursorActorResponse::input_possibly_stuck`].
+const STUCK_INPUT_ACTIONS: u32 = 5;
+const STUCK_INPUT_AFTER: Duration = Duration::from_secs(30);
+
+/// Stuck input is logged at most this often, as it stays stuck until fixed.
+const STUCK_INPUT_WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often [`CursorActor::stats`] is updated while recording.
+pub const STATS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Clone)]
+pub struct Cursor {
+    /// `None` when images are kept in memory, in which case the PNG is in
+    /// [`CursorActorResponse::cursor_images`] under `id`.
+    pub file_name: Option<String>,
+    pub id: u32,
+    /// Position of the click point as a fraction of the image size.
+    pub hotspot: XY<f64>,
+    /// The click point in image pixels, from the top left.
+    pub hotspot_px: XY<u32>,
+    pub kind: CursorKind,
+    /// See [`CursorData::system_name`].
+    pub system_name: Option<String>,
+    /// Image pixels per logical point, eg. 2.0 on a Retina display.
+    pub scale: f64,
+    /// Size of the image in pixels, the space `hotspot_px` is in.
+    pub resolution: XY<u32>,
+    /// How big the cursor appeared on screen, as a fraction of the first monitor's size
+    /// like event positions are, so it can be drawn at its real size over the video.
+    pub screen_size: XY<f64>,
+    pub animation: Option<CursorAnimation>,
+    /// Set when the OS reported that the cursor changed while this one kept being handed
+    /// out, so some events using it may have shown a different cursor on screen. Only
+    /// macOS can tell, see [`CursorSource::cursor_stale`].
+    pub possibly_stale: bool,
+}
+
+#[derive(Clone)]
+pub struct CursorAnimation {
+    /// Every frame in order, starting with the cursor's own `file_name`. Further frames
+    /// are saved as `cursor_{id}_{frame}.png`, or whichever extension the format uses.
+    pub frame_files: Vec<String>,
+    pub frame_duration_ms: f64,
+}
+
+impl CursorAnimation {
+    fn new(id: u32, data: &CursorData, format: CursorImageFormat) -> Option<Self> {
+        if data.frames.is_empty() {
+            return None;
+        }
+
+        let extension = format.extension();
+        let frame_files = std::iter::once(format!("cursor_{id}.{extension}"))
+            .chain((1..=data.frames.len()).map(|frame| format!("cursor_{id}_{frame}.{extension}")))
+            .collect();
+
+        Some(Self {
+            frame_files,
+            frame_duration_ms: data.frame_duration_ms,
+        })
+    }
+}
+
+/// How cursor images are saved to disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CursorImageFormat {
+    #[default]
+    Png,
+    /// Lossless WebP, which keeps transparency and is usually smaller than PNG.
+    Webp,
+}
+
+impl CursorImageFormat {
+    /// The images are saved with `image`'s format detection, so this also picks the
+    /// encoder.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+/// What a cursor means, as far as the OS will tell. Cursors are still deduplicated by
+/// their image, so two cursors of the same kind can have different images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CursorKind {
+    Arrow,
+    IBeam,
+    PointingHand,
+    OpenHand,
+    ClosedHand,
+    Crosshair,
+    NotAllowed,
+    /// The system's busy cursors, including the arrow with a spinner that's shown while
+    /// an app starts. Switching to and from them is recorded as
+    /// [`CursorActorResponse::busy_events`].
+    Wait,
+    ResizeLeftRight,
+    ResizeUpDown,
+    /// Diagonal resize from the top left to the bottom right corner.
+    ResizeNwse,
+    /// Diagonal resize from the top right to the bottom left corner.
+    ResizeNesw,
+    Move,
+    /// App-defined cursors, and any the platform can't identify.
+    #[default]
+    Custom,
+}
+
+/// Cursors by [`cursor_image_hash`] of their image.
+pub type Cursors = HashMap<u64, Cursor>;
+
+/// Bumped whenever [`cursor_image_hash`] changes. Cursors keyed by another version
+/// never match newly captured images, so they'd be saved again under new ids.
+pub const CURSOR_HASH_VERSION: u32 = 1;
+
+/// Identifies a cursor by its image as captured: the first 8 bytes of its SHA-256.
+/// Unlike `DefaultHasher`, this is the same across builds and Rust versions, so
+/// [`Cursors`] kept from an earlier session still match.
+pub fn cursor_image_hash(image: &[u8]) -> u64 {
+    let digest = Sha256::digest(image);
+    u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 is 32 bytes"))
+}
+
+/// A single event as it's recorded, for live consumers such as a cursor trail preview.
+#[derive(Debug, Clone)]
+pub enum CursorEvent {
+    Move(CursorMoveEvent),
+    Click(CursorClickEvent),
+    Scroll(CursorScrollEvent),
+    Drag(CursorDragEvent),
+    Modifier(ModifierEvent),
+    Busy(BusyStateEvent),
+}
+
+/// Receives events as they're recorded, in place of buffering them in the response, see
+/// [`CursorRecorderConfig::sink`]. Called on the polling loop, so implementations should
+/// hand slow work off elsewhere. Events the filter drops never reach the sink.
+pub trait CursorEventSink {
+    fn on_move(&mut self, event: CursorMoveEvent);
+
+    fn on_click(&mut self, event: CursorClickEvent);
+
+    fn on_scroll(&mut self, _event: CursorScrollEvent) {}
+
+    fn on_drag(&mut self, _event: CursorDragEvent) {}
+
+    fn on_modifier(&mut self, _event: ModifierEvent) {}
+
+    fn on_busy(&mut self, _event: BusyStateEvent) {}
+
+    /// Called once after the last event when the recorder stops, but not when it's
+    /// aborted.
+    fn on_stop(&mut self, _summary: &CursorStats) {}
+}
+
+/// The default sink, buffering events to be returned by [`CursorActor::stop`].
+impl CursorEventSink for CursorActorResponse {
+    fn on_move(&mut self, event: CursorMoveEvent) {
+        self.moves.push(event);
+    }
+
+    fn on_click(&mut self, event: CursorClickEvent) {
+        self.clicks.push(event);
+    }
+
+    fn on_scroll(&mut self, event: CursorScrollEvent) {
+        self.scrolls.push(event);
+    }
+
+    fn on_drag(&mut self, event: CursorDragEvent) {
+        self.drags.push(event);
+    }
+
+    fn on_modifier(&mut self, event: ModifierEvent) {
+        self.modifier_events.push(event);
+    }
+
+    fn on_busy(&mut self, event: BusyStateEvent) {
+        self.busy_events.push(event);
+    }
+}
+
+#[derive(Default)]
+pub struct CursorActorResponse {
+    /// PNG of each cursor by id, when recording with
+    /// [`CursorRecorderConfig::keep_images_in_memory`] or
+    /// [`CursorRecorderConfig::retain_saved_images`].
+    pub cursor_images: HashMap<u32, Vec<u8>>,
+    pub cursors: Cursors,
+    pub next_cursor_id: u32,
+    /// Cursor images this recorder wrote to disk, not counting animation frames. Each
+    /// new cursor is written once, however often it reappears, and cursors carried over
+    /// from a previous segment aren't written again.
+    pub cursor_files_written: u32,
+    pub moves: Vec<CursorMoveEvent>,
+    pub clicks: Vec<CursorClickEvent>,
+    pub scrolls: Vec<CursorScrollEvent>,
+    /// Higher level view of the clicks and moves above, which are still recorded as-is.
+    pub drags: Vec<CursorDragEvent>,
+    /// How many times each button was pressed. Releases aren't counted, and presses are
+    /// counted even if the filter or a sink kept them out of `clicks`.
+    pub click_counts: HashMap<MouseButton, u64>,
+    /// Empty unless enabled with [`CursorRecorderConfig::record_modifiers`].
+    pub modifier_events: Vec<ModifierEvent>,
+    /// Every time the cursor turned into a [`CursorKind::Wait`] cursor or back, so the
+    /// editor can mark where the app was loading. Only noticed while cursor images are
+    /// captured, and a recording that ends busy has no event for leaving it.
+    pub busy_events: Vec<BusyStateEvent>,
+    /// Set when the cursors directory couldn't be created. Moves and clicks are still
+    /// recorded, but every event uses the default cursor.
+    pub image_capture_disabled: bool,
+    /// Set when [`CursorRecorderConfig::max_cursors`] was reached, after which new cursors
+    /// were recorded as the default cursor.
+    pub cursor_limit_reached: bool,
+    /// Set when the position stopped changing at all while buttons and the wheel were
+    /// still being used, which is how losing input permissions or a sleeping display
+    /// tends to look: `device_query` keeps returning the same position, often the
+    /// screen's corner. It's a guess, and every event is still recorded as it came in.
+    pub input_possibly_stuck: bool,
+    /// Set when the recorder stopped itself after
+    /// [`CursorRecorderConfig::auto_stop_after_idle`] without any input.
+    pub auto_stopped: bool,
+    /// How long recording ran, not counting pauses, measured from the same start as
+    /// `process_time_ms`. That includes time before the recorder started when it's
+    /// timed from [`CursorRecorderConfig::start_time`].
+    pub duration: Duration,
+    /// Ticks sampled, not counting pauses. Far fewer than `duration` divided by the poll
+    /// interval means sampling fell behind.
+    pub sample_count: u64,
+    /// Ticks that took more than twice the poll interval to sample, such as when fetching
+    /// a cursor image was slow. Each stretches the gap until the next sample, so a high
+    /// count explains unevenly spaced events.
+    pub overruns: u64,
+    /// Every time [`CursorRecorderConfig::adaptive_polling`] switched intervals, in order.
+    /// Recording starts at the regular poll interval.
+    pub poll_interval_changes: Vec<PollIntervalChange>,
+    /// Every [`CursorActor::update_bounds`], in order. Events before a change stay
+    /// normalized against the bounds in effect when they were recorded.
+    pub bounds_changes: Vec<BoundsChange>,
+    /// Events left out of the [`CursorRecorderConfig::events`] stream because the
+    /// consumer fell behind. Only counted once recording stops.
+    pub dropped_stream_events: u64,
+    /// Where the cursor was when the recorder started, so there's a known position from
+    /// the first frame even if it never moves. Timed at 0, or from
+    /// [`CursorRecorderConfig::start_time`] when one is shared.
+    pub start: Option<CursorMarker>,
+    /// Where the cursor was when recording stopped, timed at the stop. Missing if the
+    /// recorder didn't stop in time and only partial results were returned.
+    pub end: Option<CursorMarker>,
+}
+
+/// Running totals for showing recording progress, see [`CursorActor::stats`]. Events
+/// count once recorded, so moves still being coalesced aren't included yet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CursorStats {
+    /// Ticks sampled, like [`CursorActorResponse::sample_count`].
+    pub samples: u64,
+    pub moves: u64,
+    pub clicks: u64,
+    /// Distinct cursor images, including ones carried over from previous segments.
+    pub cursors: u64,
+    pub overruns: u64,
+}
+
+/// The poll interval in effect from `process_time_ms` on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PollIntervalChange {
+    pub process_time_ms: f64,
+    pub interval: Duration,
+}
+
+/// The first monitor's bounds in effect from `process_time_ms` on.
+#[derive(Debug, Clone, Copy)]
+pub struct BoundsChange {
+    pub process_time_ms: f64,
+    pub bounds: Bounds,
+}
+
+/// The cursor's state at the edge of a recording. Markers are taken regardless of the
+/// move threshold, pausing or `drop_out_of_bounds`, and positions outside every monitor
+/// are clamped to the first one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorMarker {
+    pub process_time_ms: f64,
+    pub unix_time_ms: f64,
+    pub x: f64,
+    pub y: f64,
+    pub monitor: Option<u32>,
+    pub visible: bool,
+}
+
+impl CursorMarker {
+    fn new(
+        coords: (i32, i32),
+        visible: bool,
+        monitors: &[Bounds],
+        process_time_ms: f64,
+    ) -> Option<Self> {
+        let Position { x, y, monitor } = normalize_position(coords, monitors, false)?;
+
+        Some(Self {
+            process_time_ms,
+            unix_time_ms: chrono::Utc::now().timestamp_millis() as f64,
+            x,
+            y,
+            monitor,
+            visible,
+        })
+    }
+}
+
+impl CursorActorResponse {
+    /// The PNG of the cursor with `id`, if this recorder kept it in memory. Cursors
+    /// carried over from a previous segment aren't, their images are only on disk.
+    pub fn image_bytes(&self, id: u32) -> Option<&[u8]> {
+        self.cursor_images.get(&id).map(Vec::as_slice)
+    }
+
+    /// Drops events that ended before `cutoff_ms`. Events are recorded in time order, so
+    /// this only ever looks past the first one when something actually expired.
+    fn discard_before(&mut self, cutoff_ms: f64) {
+        fn expired<T>(events: &mut Vec<T>, cutoff_ms: f64, time: impl Fn(&T) -> f64) {
+            let count = events.iter().take_while(|e| time(e) < cutoff_ms).count();
+            events.drain(..count);
+        }
+
+        expired(&mut self.moves, cutoff_ms, |e| e.process_time_ms);
+        expired(&mut self.clicks, cutoff_ms, |e| e.process_time_ms);
+        expired(&mut self.scrolls, cutoff_ms, |e| e.process_time_ms);
+        expired(&mut self.drags, cutoff_ms, |e| e.end_process_time_ms);
+        expired(&mut self.modifier_events, cutoff_ms, |e| e.process_time_ms);
+        expired(&mut self.busy_events, cutoff_ms, |e| e.process_time_ms);
+    }
+
+    /// A copy of the buffered events along with every cursor and image they could refer
+    /// to, leaving this response as it was.
+    fn snapshot(&self) -> CursorActorResponse {
+        CursorActorResponse {
+            cursor_images: self.cursor_images.clone(),
+            cursors: self.cursors.clone(),
+            next_cursor_id: self.next_cursor_id,
+            moves: self.moves.clone(),
+            clicks: self.clicks.clone(),
+            scrolls: self.scrolls.clone(),
+            drags: self.drags.clone(),
+            modifier_events: self.modifier_events.clone(),
+            busy_events: self.busy_events.clone(),
+            start: self.start,
+            ..Default::default()
+        }
+    }
+
+    /// Splits the response into what's written to disk for this segment and the state
+    /// the next segment's recorder continues from.
+    pub fn into_project_cursors(self) -> ProjectCursors {
+        ProjectCursors {
+            events: CursorEvents {
+                clicks: self.clicks,
+                moves: self.moves,
+                scrolls: self.scrolls,
+                drags: self.drags,
+                modifier_events: self.modifier_events,
+                busy_events: self.busy_events,
+            },
+            cursors: self.cursors,
+            next_cursor_id: self.next_cursor_id,
+            hash_version: CURSOR_HASH_VERSION,
+        }
+    }
+
+    /// Thins `moves` out to about `target_hz` samples per second. The moves either side
+    /// of each click, sharp turns, and changes of cursor or visibility are always kept,
+    /// as are the first and last move, so paths and clicks still line up. Clicks and
+    /// every other event are left alone. Does nothing unless `target_hz` is positive.
+    pub fn decimate(&mut self, target_hz: f64) {
+        if target_hz.is_nan() || target_hz <= 0.0 || self.moves.len() <= 2 {
+            return;
+        }
+
+        let interval_ms = 1000.0 / target_hz;
+        let moves = &self.moves;
+
+        let mut keep = self.anchors();
+        let mut last_kept = 0;
+        for i in 1..moves.len() - 1 {
+            let (last, current) = (&moves[last_kept], &moves[i]);
+            keep[i] |= current.process_time_ms - last.process_time_ms >= interval_ms
+                || current.cursor_id != last.cursor_id
+                || current.visible != last.visible
+                || is_sharp_turn(last, current, &moves[i + 1]);
+
+            if keep[i] {
+                last_kept = i;
+            }
+        }
+
+        let mut keep = keep.into_iter();
+        self.moves.retain(|_| keep.next().unwrap_or(true));
+    }
+
+    /// Evens out jitter in `moves` by averaging each position with the moves around it,
+    /// `window` moves in all. Only moves on the same monitor are averaged together. The
+    /// first and last move and the moves either side of each click keep their exact
+    /// positions so clicks still land where they happened, and everything but `x` and
+    /// `y` is left alone. Does nothing for a `window` of 1 or less.
+    pub fn smooth(&mut self, window: usize) {
+        if window <= 1 || self.moves.len() <= 2 {
+            return;
+        }
+
+        let anchors = self.anchors();
+        let half = window / 2;
+        let moves = &self.moves;
+
+        let smoothed: Vec<_> = (0..moves.len())
+            .map(|i| {
+                if anchors[i] {
+                    return (moves[i].x, moves[i].y);
+                }
+
+                let neighbours = moves[i.saturating_sub(half)..(i + half + 1).min(moves.len())]
+                    .iter()
+                    .filter(|m| m.monitor == moves[i].monitor);
+                let (mut x, mut y, mut count) = (0.0, 0.0, 0.0);
+                for m in neighbours {
+                    x += m.x;
+                    y += m.y;
+                    count += 1.0;
+                }
+                (x / count, y / count)
+            })
+            .collect();
+
+        for (m, (x, y)) in self.moves.iter_mut().zip(smoothed) {
+            m.x = x;
+            m.y = y;
+        }
+    }
+
+    /// Replaces `moves` with one per video frame at `fps`, interpolated between the
+    /// recorded moves, so a renderer compositing the cursor per frame can use them as-is.
+    /// Frames run from the first move to the end of the recording, holding the last
+    /// position once the cursor comes to rest. Everything but the position comes from the
+    /// last move before each frame, and moves between monitors aren't interpolated.
+    /// Velocities are measured between frames. Each click's `frame` is set to the frame
+    /// nearest to it. Does nothing unless `fps` is positive and finite.
+    pub fn resample_to_frames(&mut self, fps: f64) {
+        let (Some(first), Some(last)) = (self.moves.first(), self.moves.last()) else {
+            return;
+        };
+        if !fps.is_finite() || fps <= 0.0 {
+            return;
+        }
+
+        let frame_ms = 1000.0 / fps;
+        let end_ms = last
+            .process_time_ms
+            .max(self.duration.as_secs_f64() * 1000.0);
+        let first_frame = (first.process_time_ms / frame_ms).ceil().max(0.0) as u64;
+        let last_frame = ((end_ms / frame_ms).floor() as u64).max(first_frame);
+
+        let moves = &self.moves;
+        let mut motion = Motion::default();
+        let mut current = 0;
+        let resampled = (first_frame..=last_frame)
+            .map(|frame| {
+                let time_ms = frame as f64 * frame_ms;
+                while moves
+                    .get(current + 1)
+                    .is_some_and(|next| next.process_time_ms <= time_ms)
+                {
+                    current += 1;
+                }
+
+                let before = &moves[current];
+                let mut event = before.clone();
+                if let Some(after) = moves
+                    .get(current + 1)
+                    .filter(|after| after.monitor == before.monitor)
+                {
+                    let t = (time_ms - before.process_time_ms)
+                        / (after.process_time_ms - before.process_time_ms);
+                    let lerp = |a: f64, b: f64| a + (b - a) * t;
+                    event.x = lerp(before.x, after.x);
+                    event.y = lerp(before.y, after.y);
+                    event.screen_x =
+                        lerp(before.screen_x as f64, after.screen_x as f64).round() as i32;
+                    event.screen_y =
+                        lerp(before.screen_y as f64, after.screen_y as f64).round() as i32;
+                }
+                event.unix_time_ms = before.unix_time_ms + (time_ms - before.process_time_ms);
+                event.process_time_ms = time_ms;
+
+                let (velocity, acceleration) =
+                    motion.sample(event.x, event.y, event.monitor, time_ms);
+                event.velocity_x = velocity.x;
+                event.velocity_y = velocity.y;
+                event.acceleration_x = acceleration.x;
+                event.acceleration_y = acceleration.y;
+                event
+            })
+            .collect();
+        self.moves = resampled;
+
+        for click in &mut self.clicks {
+            click.frame = Some((click.process_time_ms / frame_ms).round().max(0.0) as u32);
+        }
+    }
+
+    /// Appends `other`, recorded after this response, as if both were one recording.
+    /// `other`'s `process_time_ms` are shifted by `time_offset`, usually this recording's
+    /// duration. Cursors with the same image are merged into this response's, and the
+    /// rest get new ids where theirs are already taken, with events updated to match.
+    /// File names are kept as they were, so cursors saved to another segment's directory
+    /// still have to be looked up there.
+    pub fn merge(mut self, mut other: Self, time_offset: f64) -> Self {
+        let mut next_id = self
+            .cursors
+            .values()
+            .chain(other.cursors.values())
+            .map(|cursor| cursor.id + 1)
+            .chain([self.next_cursor_id, other.next_cursor_id])
+            .max()
+            .unwrap_or_default();
+        let taken: Vec<_> = self.cursors.values().map(|cursor| cursor.id).collect();
+
+        let mut remapped = HashMap::new();
+        for (hash, mut cursor) in other.cursors {
+            let new_id = match self.cursors.get(&hash) {
+                Some(existing) => existing.id,
+                None if taken.contains(&cursor.id) => {
+                    let id = next_id;
+                    next_id += 1;
+                    id
+                }
+                None => cursor.id,
+            };
+
+            if let Some(image) = other.cursor_images.remove(&cursor.id) {
+                self.cursor_images.entry(new_id).or_insert(image);
+            }
+            if new_id != cursor.id {
+                remapped.insert(cursor.id.to_string(), new_id.to_string());
+            }
+
+            cursor.id = new_id;
+            self.cursors.entry(hash).or_insert(cursor);
+        }
+        self.next_cursor_id = next_id;
+
+        let remap = |cursor_id: &mut String| {
+            if let Some(new_id) = remapped.get(cursor_id.as_str()) {
+                cursor_id.clone_from(new_id);
+            }
+        };
+        for event in &mut other.moves {
+            event.process_time_ms += time_offset;
+            remap(&mut event.cursor_id);
+        }
+        for event in &mut other.clicks {
+            event.process_time_ms += time_offset;
+            remap(&mut event.cursor_id);
+        }
+        for event in &mut other.scrolls {
+            event.process_time_ms += time_offset;
+        }
+        for event in &mut other.drags {
+            event.start_process_time_ms += time_offset;
+            event.end_process_time_ms += time_offset;
+        }
+        for event in &mut other.modifier_events {
+            event.process_time_ms += time_offset;
+        }
+        for event in &mut other.busy_events {
+            event.process_time_ms += time_offset;
+        }
+        for change in &mut other.poll_interval_changes {
+            change.process_time_ms += time_offset;
+        }
+
+        self.moves.append(&mut other.moves);
+        self.clicks.append(&mut other.clicks);
+        self.scrolls.append(&mut other.scrolls);
+        self.drags.append(&mut other.drags);
+        self.modifier_events.append(&mut other.modifier_events);
+        self.busy_events.append(&mut other.busy_events);
+        self.poll_interval_changes
+            .append(&mut other.poll_interval_changes);
+        for change in &mut other.bounds_changes {
+            change.process_time_ms += time_offset;
+        }
+        self.bounds_changes.append(&mut other.bounds_changes);
+
+        let other_end = Duration::from_secs_f64((time_offset / 1000.0).max(0.0)) + other.duration;
+        self.duration = self.duration.max(other_end);
+        self.sample_count += other.sample_count;
+        self.overruns += other.overruns;
+        self.dropped_stream_events += other.dropped_stream_events;
+        self.cursor_files_written += other.cursor_files_written;
+        for (button, count) in other.click_counts {
+            *self.click_counts.entry(button).or_default() += count;
+        }
+        self.image_capture_disabled |= other.image_capture_disabled;
+        self.cursor_limit_reached |= other.cursor_limit_reached;
+        self.input_possibly_stuck |= other.input_possibly_stuck;
+        self.auto_stopped |= other.auto_stopped;
+        self.start = self.start.or(other.start.map(|mut start| {
+            start.process_time_ms += time_offset;
+            start
+        }));
+        self.end = other
+            .end
+            .map(|mut end| {
+                end.process_time_ms += time_offset;
+                end
+            })
+            .or(self.end);
+
+        self
+    }
+
+    /// Draws `moves` as a path on a `width` by `height` SVG canvas, with a dot where each
+    /// press landed, for thumbnails and eyeballing recordings. Positions are normalized
+    /// per monitor, so every monitor's moves fill the whole canvas, and the path breaks
+    /// wherever the cursor switches monitors.
+    pub fn to_svg(&self, width: u32, height: u32) -> String {
+        let point = |x: f64, y: f64| (x * width as f64, y * height as f64);
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        );
+        for path in self.moves.chunk_by(|a, b| a.monitor == b.monitor) {
+            let points: Vec<_> = path
+                .iter()
+                .map(|m| {
+                    let (x, y) = point(m.x, m.y);
+                    format!("{x:.1},{y:.1}")
+                })
+                .collect();
+            svg += &format!(
+                r#"<polyline points="{}" fill="none" stroke="black" stroke-width="1"/>"#,
+                points.join(" ")
+            );
+        }
+        for click in self.clicks.iter().filter(|click| click.down) {
+            let (x, y) = point(click.x, click.y);
+            svg += &format!(r#"<circle cx="{x:.1}" cy="{y:.1}" r="3" fill="red"/>"#);
+        }
+        svg += "</svg>";
+
+        svg
+    }
+
+    /// Hands every buffered event to `on_event` in time order, waiting out the gaps
+    /// between their `process_time_ms` divided by `speed`, so 2.0 replays at double
+    /// speed. The first event comes immediately, and each drag comes at its release.
+    /// Events are sorted by time first, keeping ties in recorded order, so out of order
+    /// timestamps like those of merged segments replay in order rather than stalling.
+    /// A `speed` that isn't positive and finite replays without waiting.
+    pub async fn replay(&self, speed: f64, mut on_event: impl FnMut(&CursorEvent)) {
+        let mut events: Vec<(f64, CursorEvent)> = self
+            .moves
+            .iter()
+            .map(|e| (e.process_time_ms, CursorEvent::Move(e.clone())))
+            .chain(
+                self.clicks
+                    .iter()
+                    .map(|e| (e.process_time_ms, CursorEvent::Click(e.clone()))),
+            )
+            .chain(
+                self.scrolls
+                    .iter()
+                    .map(|e| (e.process_time_ms, CursorEvent::Scroll(e.clone()))),
+            )
+            .chain(
+                self.drags
+                    .iter()
+                    .map(|e| (e.end_process_time_ms, CursorEvent::Drag(e.clone()))),
+            )
+            .chain(
+                self.modifier_events
+                    .iter()
+                    .map(|e| (e.process_time_ms, CursorEvent::Modifier(e.clone()))),
+            )
+            .chain(
+                self.busy_events
+                    .iter()
+                    .map(|e| (e.process_time_ms, CursorEvent::Busy(e.clone()))),
+            )
+            .collect();
+        events.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        let realtime = speed > 0.0 && speed.is_finite();
+        // Timestamps that aren't numbers sort to the ends and are never waited for
+        let first_ms = events
+            .iter()
+            .map(|(time_ms, _)| *time_ms)
+            .find(|time_ms| time_ms.is_finite())
+            .unwrap_or_default();
+        let start = tokio::time::Instant::now();
+        for (time_ms, event) in &events {
+            let deadline = Duration::try_from_secs_f64((time_ms - first_ms) / 1000.0 / speed)
+                .ok()
+                .and_then(|offset| start.checked_add(offset));
+            if let Some(deadline) = deadline.filter(|_| realtime) {
+                tokio::time::sleep_until(deadline).await;
+            }
+            on_event(event);
+        }
+    }
+
+    /// Moves that post-processing must keep as they are: the first and last, and the ones
+    /// either side of each click.
+    fn anchors(&self) -> Vec<bool> {
+        let moves = &self.moves;
+
+        let mut anchors = vec![false; moves.len()];
+        if let Some(last) = anchors.len().checked_sub(1) {
+            anchors[0] = true;
+            anchors[last] = true;
+        }
+        for click in &self.clicks {
+            let after = moves.partition_point(|m| m.process_time_ms <= click.process_time_ms);
+            if let Some(before) = after.checked_sub(1) {
+                anchors[before] = true;
+            }
+            if let Some(anchor) = anchors.get_mut(after) {
+                *anchor = true;
+            }
+        }
+
+        anchors
+    }
+}
+
+/// Whether the path bends by more than 45 degrees at `current`.
+fn is_sharp_turn(
+    prev: &CursorMoveEvent,
+    current: &CursorMoveEvent,
+    next: &CursorMoveEvent,
+) -> bool {
+    let (in_x, in_y) = (current.x - prev.x, current.y - prev.y);
+    let (out_x, out_y) = (next.x - current.x, next.y - current.y);
+
+    let lengths = (in_x.hypot(in_y)) * (out_x.hypot(out_y));
+    lengths > 0.0 && (in_x * out_x + in_y * out_y) / lengths < std::f64::consts::FRAC_1_SQRT_2
+}
+
+pub struct ProjectCursors {
+    /// Contents of the segment's `cursor.json`.
+    pub events: CursorEvents,
+    pub cursors: Cursors,
+    pub next_cursor_id: u32,
+    /// [`CURSOR_HASH_VERSION`] that `cursors` are keyed by, for storing alongside them.
+    pub hash_version: u32,
+}
+
+/// Lists cursor images the way the recording meta does, keyed by cursor id. `images_dir`
+/// is where the images were saved, relative to the recording directory. Cursors kept in
+/// memory have no file to point to and are left out.
+pub fn project_cursor_meta(cursors: &Cursors, images_dir: &RelativePath) -> cap_project::Cursors {
+    cap_project::Cursors::Correct(
+        cursors
+            .values()
+            .filter_map(|cursor| {
+                let file_name = cursor.file_name.as_ref()?;
+
+                Some((
+                    cursor.id.to_string(),
+                    CursorMeta {
+                        image_path: images_dir.join(file_name),
+                        hotspot: cursor.hotspot,
+                        scale: cursor.scale,
+                        animation: cursor
+                            .animation
+                            .as_ref()
+                            .map(|animation| CursorAnimationMeta {
+                                frame_paths: animation
+                                    .frame_files
+                                    .iter()
+                                    .map(|file_name| images_dir.join(file_name))
+                                    .collect(),
+                                frame_duration_ms: animation.frame_duration_ms,
+                            }),
+                    },
+                ))
+            })
+            .collect(),
+    )
+}
+
+/// Deletes the cursor images in `cursors_dir` that none of `cursors` use, like ones left
+/// behind by an earlier recording into the same directory. Only files named like the
+/// recorder's own images are touched. Returns how many were removed.
+pub fn prune_unused_cursors(cursors_dir: &Path, cursors: &Cursors) -> std::io::Result<usize> {
+    let used: HashSet<&str> = cursors
+        .values()
+        .flat_map(|cursor| {
+            let frame_files = cursor.animation.iter().flat_map(|a| &a.frame_files);
+            cursor.file_name.iter().chain(frame_files)
+        })
+        .map(String::as_str)
+        .collect();
+
+    let entries = match std::fs::read_dir(cursors_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut removed = 0;
+    for entry in entries {
+        let path = entry?.path();
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !is_cursor_file(file_name) || used.contains(file_name) {
+            continue;
+        }
+
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                debug!("Removed unused cursor image {}", path.display());
+                removed += 1;
+            }
+            Err(e) => error!(
+                "Failed to remove unused cursor image {}: {e}",
+                path.display()
+            ),
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Deletes the image files of `cursors`, along with any animation frames.
+fn remove_cursor_files<'a>(cursors_dir: &Path, cursors: impl Iterator<Item = &'a Cursor>) {
+    // An animation's first frame is the cursor's own file
+    let files = cursors.flat_map(|cursor| match &cursor.animation {
+        Some(animation) => animation.frame_files.as_slice(),
+        None => cursor.file_name.as_slice(),
+    });
+
+    for file_name in files {
+        let path = cursors_dir.join(file_name);
+        if let Err(e) = std::fs::remove_file(&path) {
+            error!("Failed to remove cursor image {}: {e}", path.display());
+        }
+    }
+}
+
+fn update_cursor_sidecar(cursors_dir: &Path, cursors: &Cursors) {
+    if let Err(e) = write_cursor_sidecar(cursors_dir, cursors) {
+        error!(
+            "Failed to write the cursor sidecar to {}: {e}",
+            cursors_dir.display()
+        );
+    }
+}
+
+fn is_cursor_file(file_name: &str) -> bool {
+    let Some((stem, extension)) = file_name.rsplit_once('.') else {
+        return false;
+    };
+
+    stem.strip_prefix("cursor_")
+        .is_some_and(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit() || c == '_'))
+        && [CursorImageFormat::Png, CursorImageFormat::Webp]
+            .iter()
+            .any(|format| format.extension() == extension)
+}
+
+/// Everything needed to start a cursor recorder. Only the screen bounds and cursors
+/// directory are required, everything else has a default that suits a fresh recording.
+pub struct CursorRecorderConfig {
+    monitors: Vec<Bounds>,
+    monitor_ids: Option<Vec<u32>>,
+    window_bounds: Option<(WindowBounds, Duration)>,
+    cursors_dir: PathBuf,
+    prev_cursors: Cursors,
+    next_cursor_id: u32,
+    poll_interval: Duration,
+    capture_images: bool,
+    warm_up_capture: bool,
+    keep_images_in_memory: bool,
+    retain_saved_images: bool,
+    image_format: CursorImageFormat,
+    cursor_resolution: Option<u32>,
+    normalize_size: Option<(u32, u32)>,
+    events: Option<mpsc::Sender<CursorEvent>>,
+    drop_out_of_bounds: bool,
+    move_threshold: f64,
+    coalesce_window: Duration,
+    double_click_interval: Duration,
+    double_click_radius: f64,
+    spill: Option<(PathBuf, usize)>,
+    journal: Option<(PathBuf, Duration)>,
+    replay_window: Option<Duration>,
+    start_time: Option<Instant>,
+    time_offset_ms: f64,
+    similar_cursor_distance: Option<u32>,
+    max_cursors: usize,
+    image_hasher: ImageHasher,
+    record_moves: bool,
+    grab_cursor_on_click: bool,
+    recorded_buttons: Option<HashSet<MouseButton>>,
+    record_motion_deltas: bool,
+    record_modifiers: bool,
+    adaptive_polling: Option<(Duration, u32)>,
+    auto_stop_after_idle: Option<Duration>,
+    filter: Option<EventFilter>,
+    sink: Option<Box<dyn CursorEventSink + Send>>,
+    prune_unused_cursors: bool,
+}
+
+impl CursorRecorderConfig {
+    pub fn new(screen_bounds: Bounds, cursors_dir: PathBuf) -> Self {
+        Self {
+            monitors: vec![screen_bounds],
+            monitor_ids: None,
+            window_bounds: None,
+            cursors_dir,
+            prev_cursors: Cursors::new(),
+            next_cursor_id: 0,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            capture_images: true,
+            warm_up_capture: true,
+            keep_images_in_memory: false,
+            retain_saved_images: false,
+            image_format: CursorImageFormat::default(),
+            cursor_resolution: None,
+            normalize_size: None,
+            events: None,
+            drop_out_of_bounds: false,
+            move_threshold: DEFAULT_MOVE_THRESHOLD,
+            coalesce_window: DEFAULT_COALESCE_WINDOW,
+            double_click_interval: DEFAULT_DOUBLE_CLICK_INTERVAL,
+            double_click_radius: DEFAULT_DOUBLE_CLICK_RADIUS,
+            spill: None,
+            journal: None,
+            replay_window: None,
+            start_time: None,
+            time_offset_ms: 0.0,
+            similar_cursor_distance: None,
+            max_cursors: DEFAULT_MAX_CURSORS,
+            image_hasher: Box::new(cursor_image_hash),
+            record_moves: true,
+            grab_cursor_on_click: false,
+            recorded_buttons: None,
+            record_motion_deltas: false,
+            record_modifiers: false,
+            adaptive_polling: None,
+            auto_stop_after_idle: None,
+            filter: None,
+            sink: None,
+            prune_unused_cursors: false,
+        }
+    }
+
+    /// Records across several monitors, normalizing each position within the monitor it
+    /// falls on. Replaces the screen bounds passed to [`CursorRecorderConfig::new`].
+    pub fn monitors(mut self, monitors: Vec<Bounds>) -> Self {
+        self.monitors = monitors;
+        self
+    }
+
+    /// The OS's id for each of the [`CursorRecorderConfig::monitors`], in the same order,
+    /// which events carry as [`CursorMoveEvent::monitor_id`]. Without ids, events carry
+    /// the monitor's index instead.
+    pub fn monitor_ids(mut self, monitor_ids: Vec<u32>) -> Self {
+        self.monitor_ids = Some(monitor_ids);
+        self
+    }
+
+    /// Records a single window that may move or be resized: positions are normalized
+    /// against whatever `window_bounds` returns, in screen coordinates, in place of the
+    /// first monitor. It's called once when recording starts and again at most every
+    /// `refresh_interval`, or on every tick when that's zero, on the polling loop, so it
+    /// should be cheap. Each change is applied like [`CursorActor::update_bounds`], which
+    /// it overrides on the next refresh, and empty bounds, such as those of a minimized
+    /// window, are ignored in favor of the last ones. Moves outside the window are
+    /// [`CursorMoveEvent::off_screen`] unless another monitor was given that they're on.
+    pub fn window_bounds(
+        mut self,
+        window_bounds: impl FnMut() -> Bounds + Send + 'static,
+        refresh_interval: Duration,
+    ) -> Self {
+        self.window_bounds = Some((Box::new(window_bounds), refresh_interval));
+        self
+    }
+
+    /// Continues from the cursors saved by previous segments, so images that were
+    /// already written are reused instead of being saved again under a new id. A
+    /// `next_cursor_id` that's already taken by one of `cursors` is bumped past them
+    /// when the recorder starts, so no file is overwritten.
+    pub fn prev_cursors(mut self, cursors: Cursors, next_cursor_id: u32) -> Self {
+        self.prev_cursors = cursors;
+        self.next_cursor_id = next_cursor_id;
+        self
+    }
+
+    /// How long the recorder sleeps between samples. Both move and click detection
+    /// happen once per tick, so this is the effective sampling cadence for all events.
+    /// Lower values (eg. 8ms) suit high-FPS recordings, higher ones (eg. 33ms) save battery.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Backs off to polling every `idle_interval` once `idle_after` ticks in a row had no
+    /// movement, clicks or scrolls, and goes back to the regular poll interval on the
+    /// first tick with any. Movement starting while idle is only seen on the next idle
+    /// tick, so `idle_interval` bounds how late a gesture's first move can be. Each switch
+    /// is listed in [`CursorActorResponse::poll_interval_changes`].
+    pub fn adaptive_polling(mut self, idle_interval: Duration, idle_after: u32) -> Self {
+        self.adaptive_polling = Some((idle_interval, idle_after));
+        self
+    }
+
+    /// Stops recording on its own once nothing has moved, been clicked, scrolled or
+    /// typed for `idle`, like [`CursorActor::stop`] would, for unattended captures that
+    /// shouldn't keep going after the user walked away. Time spent paused doesn't count
+    /// as idle. [`CursorActor::is_stopped`] tells when it happened, the response is still
+    /// collected with [`CursorActor::stop`], and it has
+    /// [`CursorActorResponse::auto_stopped`] set.
+    pub fn auto_stop_after_idle(mut self, idle: Duration) -> Self {
+        self.auto_stop_after_idle = Some(idle);
+        self
+    }
+
+    /// When disabled, no cursor images are saved and every event uses the default cursor.
+    /// Builds without the `cursor-images` feature always behave as if it's disabled.
+    pub fn capture_images(mut self, capture_images: bool) -> Self {
+        self.capture_images = capture_images;
+        self
+    }
+
+    /// Captures the cursor once before recording starts and throws the result away. The
+    /// first capture pays for loading the platform's cursor APIs, which otherwise lands
+    /// on the first ticks and can hold up their samples. Delays the start by that much
+    /// instead, without counting towards `process_time_ms`. On by default, and skipped
+    /// when images aren't captured.
+    pub fn warm_up_capture(mut self, warm_up_capture: bool) -> Self {
+        self.warm_up_capture = warm_up_capture;
+        self
+    }
+
+    /// Keeps cursor images in [`CursorActorResponse::cursor_images`] instead of saving
+    /// them, so nothing is written to the cursors directory, which isn't even created.
+    /// Only the first frame of animated cursors is kept.
+    pub fn keep_images_in_memory(mut self, keep_images_in_memory: bool) -> Self {
+        self.keep_images_in_memory = keep_images_in_memory;
+        self
+    }
+
+    /// Also keeps the PNG of every cursor saved to disk in
+    /// [`CursorActorResponse::cursor_images`], so a preview can be drawn from
+    /// [`CursorActorResponse::image_bytes`] without reading the files back. Each image
+    /// is held until the response is dropped. Off by default.
+    pub fn retain_saved_images(mut self, retain_saved_images: bool) -> Self {
+        self.retain_saved_images = retain_saved_images;
+        self
+    }
+
+    /// Format of the cursor images saved to disk, PNG by default. Images kept in memory
+    /// are always PNG.
+    pub fn image_format(mut self, image_format: CursorImageFormat) -> Self {
+        self.image_format = image_format;
+        self
+    }
+
+    /// Windows only: captures cursors `size` pixels square, re-read from the cursor's
+    /// resource when it has a larger image than the one on screen, so they stay sharp
+    /// when the editor scales them up. Animated cursors are captured as usual. macOS
+    /// already captures at the display's full resolution.
+    pub fn cursor_resolution(mut self, size: u32) -> Self {
+        self.cursor_resolution = Some(size);
+        self
+    }
+
+    /// Stores every cursor image at exactly `width` by `height` pixels, so the editor can
+    /// treat all cursors alike. Images are scaled with Lanczos filtering to fit while
+    /// keeping their aspect ratio, and centered on a transparent canvas, with `hotspot`
+    /// and `scale` adjusted to match, so cursors still point and appear at the same size.
+    /// Images are kept as captured by default.
+    pub fn normalize_size(mut self, width: u32, height: u32) -> Self {
+        self.normalize_size = Some((width, height));
+        self
+    }
+
+    /// Streams every event to `events` as it happens, in addition to buffering it for
+    /// [`CursorActor::stop`]. The recorder never waits on the channel: while it's full,
+    /// new events are left out of the stream and counted in
+    /// [`CursorActorResponse::dropped_stream_events`], but are still buffered, so the
+    /// response is always complete. Size the channel for how long the consumer may lag.
+    pub fn events(mut self, events: mpsc::Sender<CursorEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Only keeps events `filter` returns true for, dropping the rest before they're
+    /// buffered or streamed. Cheaper than filtering millions of events after the fact,
+    /// but it's called for every event on the polling loop, so anything slow delays
+    /// sampling. Drags are still detected from clicks the filter dropped.
+    pub fn filter(mut self, filter: impl Fn(&CursorEvent) -> bool + Send + 'static) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Hands every event to `sink` instead of buffering it, so the response from
+    /// [`CursorActor::stop`] comes back without moves, clicks, scrolls, drags or modifier
+    /// events, and spilling has nothing to spill. The [`events`](Self::events) stream and
+    /// journal still get every event.
+    pub fn sink(mut self, sink: Box<dyn CursorEventSink + Send>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// By default, positions outside every monitor (eg. on one that isn't being recorded)
+    /// are clamped to the nearest edge of the first monitor and tagged with no monitor.
+    /// When enabled, events outside the monitors are dropped instead.
+    pub fn drop_out_of_bounds(mut self, drop_out_of_bounds: bool) -> Self {
+        self.drop_out_of_bounds = drop_out_of_bounds;
+        self
+    }
+
+    /// Skips moves that are less than `move_threshold` pixels away from the last recorded
+    /// move and within `coalesce_window` of it. The point where the cursor comes to rest
+    /// is always kept, so paths still end where the gesture did.
+    pub fn coalesce_moves(mut self, move_threshold: f64, coalesce_window: Duration) -> Self {
+        self.move_threshold = move_threshold;
+        self.coalesce_window = coalesce_window;
+        self
+    }
+
+    /// Clicks of the same button that start within `interval` of the previous release
+    /// and `radius` pixels of it count towards its `click_count`.
+    pub fn double_click(mut self, interval: Duration, radius: f64) -> Self {
+        self.double_click_interval = interval;
+        self.double_click_radius = radius;
+        self
+    }
+
+    /// Keeps at most `max_buffered` events in memory, appending older ones to the file at
+    /// `path` and reading them back in when recording stops. Each spill briefly blocks
+    /// sampling while the events are written. Off by default, as a few minutes of events
+    /// fit comfortably in memory, but hours at a 10ms poll interval can reach millions.
+    /// Live consumers can use [`CursorRecorderConfig::events`] instead.
+    pub fn spill_to_disk(mut self, path: PathBuf, max_buffered: usize) -> Self {
+        self.spill = Some((path, max_buffered));
+        self
+    }
+
+    /// Also appends events to the file at `path` every `flush_interval` while recording,
+    /// so if the app crashes before [`CursorActor::stop`], events up to the last flush
+    /// can be read back with [`recover_journal`]. Each line of the file is a
+    /// [`CursorEvents`] chunk of the events recorded since the previous line. The file is
+    /// deleted once recording stops cleanly, as the response has everything it held, and
+    /// is left alone when [`CursorActor::stop_timeout`] gives up. Off by default.
+    pub fn journal(mut self, path: PathBuf, flush_interval: Duration) -> Self {
+        self.journal = Some((path, flush_interval));
+        self
+    }
+
+    /// Only keeps the events of the last `window`, for saving what just happened after
+    /// the fact like a video instant replay does. Older moves, clicks, scrolls, drags and
+    /// modifier events are discarded as recording goes on, and
+    /// [`CursorActor::snapshot`] copies out what's left at any time. The response from
+    /// [`CursorActor::stop`] also only holds the last `window`.
+    ///
+    /// Memory is bounded by the window rather than by how long recording runs: at most
+    /// one move per poll, so `window / poll_interval` moves of around 150 bytes each,
+    /// which for 30 seconds at the default 10ms interval is well under a megabyte. Clicks
+    /// and scrolls add comparatively little, and cursors and their images are kept for
+    /// the whole recording, as any event in the window may use them.
+    /// [`CursorRecorderConfig::spill_to_disk`] is ignored, since nothing builds up, and
+    /// events handed to a [`CursorRecorderConfig::sink`] aren't buffered to begin with.
+    pub fn replay_window(mut self, window: Duration) -> Self {
+        self.replay_window = Some(window);
+        self
+    }
+
+    /// Measures `process_time_ms` from `start_time` instead of from when the recorder was
+    /// spawned. Pass the same instant the video pipeline's timestamps count from, and to
+    /// every other recorder, so events line up with frames exactly rather than being off
+    /// by however long each recorder took to start. `unix_time_ms` is unaffected.
+    pub fn start_time(mut self, start_time: Instant) -> Self {
+        self.start_time = Some(start_time);
+        self
+    }
+
+    /// Adds `offset_ms` to every `process_time_ms` as events are recorded, including the
+    /// start and end markers, so the cursor track lines up with a video that started
+    /// capturing that much earlier. Velocities and double-click timing only ever compare
+    /// shifted times, so they're unaffected. [`CursorActorResponse::duration`] is still
+    /// just how long recording ran. Negative offsets are allowed, 0 by default.
+    pub fn time_offset_ms(mut self, offset_ms: f64) -> Self {
+        self.time_offset_ms = offset_ms;
+        self
+    }
+
+    /// Records cursors that look the same as an earlier one, but aren't byte for byte
+    /// identical, as that earlier cursor. `max_distance` is how many bits of the images'
+    /// 64 bit perceptual hashes may differ, where a handful already tolerates
+    /// antialiasing differences. Off by default, when only identical images are merged.
+    pub fn merge_similar_cursors(mut self, max_distance: u32) -> Self {
+        self.similar_cursor_distance = Some(max_distance);
+        self
+    }
+
+    /// Stops saving new cursors once this many are known, counting ones carried over in
+    /// [`CursorRecorderConfig::prev_cursors`], so an app cycling through endless cursor
+    /// images can't fill the disk. Any cursor after that is recorded as the default one,
+    /// and [`CursorActorResponse::cursor_limit_reached`] is set. Defaults to
+    /// [`DEFAULT_MAX_CURSORS`].
+    pub fn max_cursors(mut self, max_cursors: usize) -> Self {
+        self.max_cursors = max_cursors;
+        self
+    }
+
+    /// Hashes cursor images with `hasher` instead of [`cursor_image_hash`], to key
+    /// [`CursorActorResponse::cursors`] and tell cursors apart. Ids are handed out in the
+    /// order cursors first appear either way, so this is for tests that want to pick
+    /// the keys or force collisions. Cursors passed to
+    /// [`CursorRecorderConfig::prev_cursors`] only match if they were hashed the same.
+    pub fn image_hasher(mut self, hasher: impl Fn(&[u8]) -> u64 + Send + 'static) -> Self {
+        self.image_hasher = Box::new(hasher);
+        self
+    }
+
+    /// When disabled, no moves are recorded, only clicks, scrolls, drags and modifiers,
+    /// which is all a click heatmap needs at a fraction of the data. The cursor image is
+    /// then only captured on ticks where a button changed. Without moves there's no path
+    /// to render, resample or smooth, the cursor isn't known to be hidden, and velocities
+    /// aren't recorded anywhere. The start and end markers are still taken. On by default.
+    pub fn record_moves(mut self, record_moves: bool) -> Self {
+        self.record_moves = record_moves;
+        self
+    }
+
+    /// Grabs the cursor image again on every tick where a button went down or up, even
+    /// if the system still hands out the same cursor, so clicks carry the shape that
+    /// was showing at the time, like a grabbing hand, rather than the one the cache
+    /// holds. Costs an extra native call per click. Off by default.
+    pub fn grab_cursor_on_click(mut self, grab_cursor_on_click: bool) -> Self {
+        self.grab_cursor_on_click = grab_cursor_on_click;
+        self
+    }
+
+    /// Only records presses and releases of `buttons`, leaving every other button out of
+    /// clicks, drags and [`CursorActorResponse::click_counts`], such as to keep the
+    /// middle and side buttons out of a tutorial. An empty set records no clicks at all.
+    /// Every button is recorded by default.
+    pub fn recorded_buttons(mut self, buttons: impl IntoIterator<Item = MouseButton>) -> Self {
+        self.recorded_buttons = Some(buttons.into_iter().collect());
+        self
+    }
+
+    /// Adds the relative motion since the previous move to each one, as
+    /// [`CursorMoveEvent::delta`], for recordings where the cursor is captured and its
+    /// position stops reflecting how the mouse moved. Moves are then also recorded when
+    /// only the raw input changed. Off by default.
+    ///
+    /// | Platform | Source |
+    /// |----------|--------|
+    /// | Windows  | `WM_INPUT` raw mouse input, skipping devices that report absolute positions |
+    /// | macOS    | The mouse delta fields of the input event tap, which needs Accessibility access |
+    /// | X11, Wayland | Computed from consecutive positions |
+    ///
+    /// Where raw input isn't available, including when the listener failed to start,
+    /// deltas are computed from positions and marked as not raw.
+    pub fn record_motion_deltas(mut self, record_motion_deltas: bool) -> Self {
+        self.record_motion_deltas = record_motion_deltas;
+        self
+    }
+
+    /// Records every press and release of Ctrl, Alt, Shift and Meta in
+    /// [`CursorActorResponse::modifier_events`], for showing held keys over the video.
+    /// Modifiers already held when recording starts are pressed at its start, and ones
+    /// still held when it stops are never released. Off by default, and independent of the
+    /// `active_modifiers` every event carries.
+    pub fn record_modifiers(mut self, record_modifiers: bool) -> Self {
+        self.record_modifiers = record_modifiers;
+        self
+    }
+
+    /// Deletes cursor images in the cursors directory that aren't among the
+    /// [`CursorRecorderConfig::prev_cursors`] when recording starts, see
+    /// [`prune_unused_cursors`]. Off by default, as the directory may hold images that
+    /// are still wanted, like ones from a recording that's kept alongside.
+    pub fn prune_unused_cursors(mut self, prune_unused_cursors: bool) -> Self {
+        self.prune_unused_cursors = prune_unused_cursors;
+        self
+    }
+
+    /// Checks that a recorder spawned from this config will actually record, so the UI
+    /// can ask for missing permissions up front instead of ending up with a cursor that
+    /// never moves. Besides the checks [`CursorRecorderConfig::spawn`] makes, this checks
+    /// that the OS lets this process read the mouse, and that the cursors directory can
+    /// be written to when images are saved there, creating it like recording would.
+    pub fn preflight(&self) -> Result<(), CursorRecordError> {
+        self.validate()?;
+        input_access()?;
+
+        if self.capture_images && !self.keep_images_in_memory && cfg!(feature = "cursor-images") {
+            check_cursors_dir(&self.cursors_dir)?;
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), CursorRecordError> {
+        let idle_interval = self
+            .adaptive_polling
+            .map(|(idle_interval, _)| idle_interval);
+        if self.poll_interval.is_zero() || idle_interval.is_some_and(|idle| idle.is_zero()) {
+            return Err(CursorRecordError::ZeroPollInterval);
+        }
+
+        if self.monitors.is_empty() {
+            return Err(CursorRecordError::NoMonitors);
+        }
+
+        if let Some(ids) = &self.monitor_ids {
+            if ids.len() != self.monitors.len() {
+                return Err(CursorRecordError::MonitorIdsMismatch {
+                    ids: ids.len(),
+                    monitors: self.monitors.len(),
+                });
+            }
+        }
+
+        if !self.time_offset_ms.is_finite() {
+            return Err(CursorRecordError::InvalidTimeOffset(self.time_offset_ms));
+        }
+
+        if let Some((width, height)) = self.normalize_size {
+            if width == 0 || height == 0 {
+                return Err(CursorRecordError::ZeroCursorSize { width, height });
+            }
+        }
+
+        // Windows takes the size as an i32, reading 0 as the cursor's own size
+        if let Some(size) = self.cursor_resolution {
+            if size == 0 || size > i32::MAX as u32 {
+                return Err(CursorRecordError::InvalidCursorResolution(size));
+            }
+        }
+
+        // Positions are normalized by each monitor's size, which would make them NaN
+        if let Some(index) = self.monitors.iter().position(|bounds| !has_area(bounds)) {
+            let bounds = &self.monitors[index];
+            return Err(CursorRecordError::DegenerateMonitor {
+                index,
+                width: bounds.width,
+                height: bounds.height,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CursorRecordError {
+    #[error("Cursor poll interval must be greater than zero")]
+    ZeroPollInterval,
+
+    #[error("Cursor recorder needs at least one monitor")]
+    NoMonitors,
+
+    #[error("Got {ids} monitor ids for {monitors} monitors")]
+    MonitorIdsMismatch { ids: usize, monitors: usize },
+
+    #[error("Monitor {index} has no area ({width}x{height})")]
+    DegenerateMonitor {
+        index: usize,
+        width: f64,
+        height: f64,
+    },
+
+    #[error("Cursor time offset must be a finite number of milliseconds, got {0}")]
+    InvalidTimeOffset(f64),
+
+    #[error("Cursor images can't be normalized to {width}x{height}")]
+    ZeroCursorSize { width: u32, height: u32 },
+
+    #[error("Cursor images can't be captured at {0}px")]
+    InvalidCursorResolution(u32),
+
+    #[error("Cursor recorder stopped without sending a response")]
+    ActorStopped(#[source] oneshot::error::RecvError),
+
+    /// What had been recorded until then is lost along with the recorder.
+    #[error("Cursor recorder panicked: {0}")]
+    Panicked(String),
+
+    #[error("Failed to start the cursor recorder thread")]
+    ThreadStart(#[source] std::io::Error),
+
+    #[error("Cursor recorder thread exited without sending a response")]
+    ThreadStopped,
+
+    #[error("Cursor recording needs Accessibility permission, grant it under System Settings > Privacy & Security > Accessibility")]
+    AccessibilityDenied,
+
+    #[error("Cursor recording needs Input Monitoring permission, grant it under System Settings > Privacy & Security > Input Monitoring")]
+    InputMonitoringDenied,
+
+    #[error("Can't read the mouse: {0}")]
+    InputUnavailable(&'static str),
+
+    #[error("Can't write cursor images to {path}")]
+    CursorsDirUnwritable {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// How long [`CursorActor::stop`] waits for the recorder to wrap up.
+pub const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct CursorActor {
+    stop_signal: Arc<AtomicBool>,
+    pause_signal: Arc<AtomicBool>,
+    // Set by abort, to whether cursor files written while recording should be deleted
+    abort_signal: Arc<OnceLock<bool>>,
+    // Set by stop_timeout when it gave up and took the events itself
+    abandon_signal: Arc<AtomicBool>,
+    // Taken by the next sample
+    new_bounds: Arc<Mutex<Option<Bounds>>>,
+    rx: oneshot::Receiver<Result<CursorActorResponse, CursorRecordError>>,
+    // Events recorded so far, so they can still be taken from a stuck actor
+    recorded: Arc<Mutex<CursorActorResponse>>,
+    stats: watch::Receiver<CursorStats>,
+}
+
+impl CursorActor {
+    /// Stops emitting events until [`CursorActor::resume`] is called. Time spent paused
+    /// doesn't count towards `process_time_ms`, which keeps events aligned with a video
+    /// track that was paused at the same time.
+    pub fn pause(&self) {
+        self.pause_signal
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.pause_signal
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Replaces the first monitor's bounds, eg. after the display's resolution or scaling
+    /// changed, from the next sample on. Earlier events keep the positions they were
+    /// normalized to, and the change is listed in
+    /// [`CursorActorResponse::bounds_changes`] so they can be told apart.
+    pub fn update_bounds(&self, bounds: Bounds) -> Result<(), CursorRecordError> {
+        set_new_bounds(&self.new_bounds, bounds)
+    }
+
+    /// Live totals of what's been recorded, updated every [`STATS_INTERVAL`] while
+    /// recording and once more when it stops. Reading them doesn't touch the buffered
+    /// events or the [`CursorRecorderConfig::events`] stream.
+    pub fn stats(&self) -> watch::Receiver<CursorStats> {
+        self.stats.clone()
+    }
+
+    /// Copies the events recorded so far while recording carries on, which with
+    /// [`CursorRecorderConfig::replay_window`] is the last window of them. Moves still
+    /// being coalesced aren't included yet, and neither are cursors whose image is still
+    /// being saved. Only the events, cursors and start marker are filled in.
+    pub fn snapshot(&self) -> CursorActorResponse {
+        lock(&self.recorded).snapshot()
+    }
+
+    /// Whether the recorder was told to stop or stopped itself, see
+    /// [`CursorRecorderConfig::auto_stop_after_idle`]. Its response is still waiting for
+    /// [`CursorActor::stop`].
+    pub fn is_stopped(&self) -> bool {
+        self.stop_signal.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub async fn stop(self) -> Result<CursorActorResponse, CursorRecordError> {
+        self.stop_timeout(DEFAULT_STOP_TIMEOUT).await
+    }
+
+    /// Like [`CursorActor::stop`], but gives up waiting after `timeout` and returns what
+    /// was recorded so far. Cursors whose image was still being saved are left out, and
+    /// spilled events stay in the spill file, as does the journal, even once the recorder
+    /// gets unstuck and stops.
+    pub async fn stop_timeout(
+        self,
+        timeout: Duration,
+    ) -> Result<CursorActorResponse, CursorRecordError> {
+        self.stop_signal
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+
+        tokio::select! {
+            res = self.rx => res.map_err(CursorRecordError::ActorStopped)?,
+            _ = tokio::time::sleep(timeout) => {
+                warn!("Cursor recorder didn't stop within {timeout:?}, keeping the events recorded so far");
+                Ok(abandon(&self.recorded, &self.abandon_signal))
+            }
+        }
+    }
+
+    /// Stops recording and throws away everything recorded, for when the user cancels.
+    /// Unlike [`CursorActor::stop`], no response is assembled: spilled events aren't
+    /// read back, and the buffered events are freed as soon as the recorder stops. With
+    /// `delete_cursor_files`, the images of cursors first seen in this recording are
+    /// deleted too, while those of [`CursorRecorderConfig::prev_cursors`] are kept.
+    pub async fn abort(self, delete_cursor_files: bool) -> Result<(), CursorRecordError> {
+        self.abort_signal.set(delete_cursor_files).ok();
+        self.stop_signal
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+
+        tokio::select! {
+            res = self.rx => res.map_err(CursorRecordError::ActorStopped)?.map(drop),
+            _ = tokio::time::sleep(DEFAULT_STOP_TIMEOUT) => {
+                warn!("Cursor recorder didn't stop within {DEFAULT_STOP_TIMEOUT:?}, discarding the events recorded so far");
+                drop(std::mem::take(&mut *lock(&self.recorded)));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Records on the current tokio runtime, which must be running. Outside of one, use
+/// [`spawn_cursor_recorder_blocking`].
+pub fn spawn_cursor_recorder(
+    screen_bounds: Bounds,
+    cursors_dir: PathBuf,
+    prev_cursors: Cursors,
+    next_cursor_id: u32,
+) -> Result<CursorActor, CursorRecordError> {
+    CursorRecorderConfig::new(screen_bounds, cursors_dir)
+        .prev_cursors(prev_cursors, next_cursor_id)
+        .spawn()
+}
+
+/// Records on a dedicated thread, for callers without a tokio runtime of their own. See
+/// [`CursorRecorderConfig::spawn_blocking`].
+pub fn spawn_cursor_recorder_blocking(
+    screen_bounds: Bounds,
+    cursors_dir: PathBuf,
+    prev_cursors: Cursors,
+    next_cursor_id: u32,
+) -> Result<BlockingCursorActor, CursorRecordError> {
+    CursorRecorderConfig::new(screen_bounds, cursors_dir)
+        .prev_cursors(prev_cursors, next_cursor_id)
+        .spawn_blocking()
+}
+
+/// A recorder started with [`CursorRecorderConfig::spawn_blocking`], controlled without
+/// a tokio runtime. Behaves like [`CursorActor`] otherwise.
+pub struct BlockingCursorActor {
+    stop_signal: Arc<AtomicBool>,
+    pause_signal: Arc<AtomicBool>,
+    abort_signal: Arc<OnceLock<bool>>,
+    abandon_signal: Arc<AtomicBool>,
+    new_bounds: Arc<Mutex<Option<Bounds>>>,
+    rx: std::sync::mpsc::Receiver<Result<CursorActorResponse, CursorRecordError>>,
+    recorded: Arc<Mutex<CursorActorResponse>>,
+    stats: watch::Receiver<CursorStats>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+impl BlockingCursorActor {
+    /// See [`CursorActor::pause`].
+    pub fn pause(&self) {
+        self.pause_signal
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.pause_signal
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// See [`CursorActor::update_bounds`].
+    pub fn update_bounds(&self, bounds: Bounds) -> Result<(), CursorRecordError> {
+        set_new_bounds(&self.new_bounds, bounds)
+    }
+
+    /// See [`CursorActor::stats`].
+    pub fn stats(&self) -> watch::Receiver<CursorStats> {
+        self.stats.clone()
+    }
+
+    /// See [`CursorActor::snapshot`].
+    pub fn snapshot(&self) -> CursorActorResponse {
+        lock(&self.recorded).snapshot()
+    }
+
+    /// See [`CursorActor::is_stopped`].
+    pub fn is_stopped(&self) -> bool {
+        self.stop_signal.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Blocks until the recorder has wrapped up and its thread has exited.
+    pub fn stop(self) -> Result<CursorActorResponse, CursorRecordError> {
+        self.stop_timeout(DEFAULT_STOP_TIMEOUT)
+    }
+
+    /// See [`CursorActor::stop_timeout`]. The thread is left to exit on its own if it
+    /// doesn't respond in time.
+    pub fn stop_timeout(self, timeout: Duration) -> Result<CursorActorResponse, CursorRecordError> {
+        self.stop_signal
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+
+        match self.rx.recv_timeout(timeout) {
+            Ok(res) => {
+                self.thread.join().ok();
+                res
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                warn!("Cursor recorder didn't stop within {timeout:?}, keeping the events recorded so far");
+                Ok(abandon(&self.recorded, &self.abandon_signal))
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => Err(join_stopped(self.thread)),
+        }
+    }
+
+    /// See [`CursorActor::abort`].
+    pub fn abort(self, delete_cursor_files: bool) -> Result<(), CursorRecordError> {
+        self.abort_signal.set(delete_cursor_files).ok();
+        self.stop_signal
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+
+        match self.rx.recv_timeout(DEFAULT_STOP_TIMEOUT) {
+            Ok(res) => {
+                self.thread.join().ok();
+                res.map(drop)
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                warn!("Cursor recorder didn't stop within {DEFAULT_STOP_TIMEOUT:?}, discarding the events recorded so far");
+                drop(std::mem::take(&mut *lock(&self.recorded)));
+                Ok(())
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => Err(join_stopped(self.thread)),
+        }
+    }
+}
+
+impl CursorRecorderConfig {
+    /// Records on the current tokio runtime. Use this from async code.
+    pub fn spawn(self) -> Result<CursorActor, CursorRecordError> {
+        let source = SystemCursorSource {
+            cursor_resolution: self.cursor_resolution,
+        };
+        self.spawn_with_source(source)
+    }
+
+    /// Records on a thread of its own, which runs a small single-threaded tokio runtime
+    /// internally, so it can be used from plain threads and doesn't need a runtime to
+    /// already be running. Prefer [`CursorRecorderConfig::spawn`] from async code. The
+    /// recorded events are the same either way.
+    pub fn spawn_blocking(self) -> Result<BlockingCursorActor, CursorRecordError> {
+        let source = SystemCursorSource {
+            cursor_resolution: self.cursor_resolution,
+        };
+        self.spawn_blocking_with_source(source)
+    }
+
+    /// Like [`CursorRecorderConfig::spawn_blocking`], recording from `source`.
+    pub fn spawn_blocking_with_source<S: CursorSource>(
+        self,
+        source: S,
+    ) -> Result<BlockingCursorActor, CursorRecordError> {
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let thread = std::thread::Builder::new()
+            .name("cursor-recorder".to_string())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_time()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        started_tx.send(Err(CursorRecordError::ThreadStart(e))).ok();
+                        return;
+                    }
+                };
+
+                runtime.block_on(async move {
+                    let actor = match self.spawn_with_source(source) {
+                        Ok(actor) => actor,
+                        Err(e) => {
+                            started_tx.send(Err(e)).ok();
+                            return;
+                        }
+                    };
+
+                    let CursorActor {
+                        stop_signal,
+                        pause_signal,
+                        abort_signal,
+                        abandon_signal,
+                        new_bounds,
+                        rx: actor_rx,
+                        recorded,
+                        stats,
+                    } = actor;
+                    started_tx
+                        .send(Ok((
+                            stop_signal,
+                            pause_signal,
+                            abort_signal,
+                            abandon_signal,
+                            new_bounds,
+                            recorded,
+                            stats,
+                        )))
+                        .ok();
+
+                    // The stop signal is set from the caller's thread, so there's nothing
+                    // to do here but wait for the recorder to see it
+                    tx.send(
+                        actor_rx
+                            .await
+                            .map_err(CursorRecordError::ActorStopped)
+                            .and_then(|res| res),
+                    )
+                    .ok();
+                });
+            })
+            .map_err(CursorRecordError::ThreadStart)?;
+
+        let (stop_signal, pause_signal, abort_signal, abandon_signal, new_bounds, recorded, stats) =
+            started_rx
+                .recv()
+                .map_err(|_| CursorRecordError::ThreadStopped)??;
+
+        Ok(BlockingCursorActor {
+            stop_signal,
+            pause_signal,
+            abort_signal,
+            abandon_signal,
+            new_bounds,
+            rx,
+            recorded,
+            stats,
+            thread,
+        })
+    }
+
+    /// Records from `source` instead of the real mouse and cursor.
+    #[tracing::instrument(name = "cursor", skip_all)]
+    pub fn spawn_with_source<S: CursorSource>(
+        self,
+        mut source: S,
+    ) -> Result<CursorActor, CursorRecordError> {
+        self.validate()?;
+
+        let Self {
+            mut monitors,
+            monitor_ids,
+            mut window_bounds,
+            cursors_dir,
+            prev_cursors,
+            next_cursor_id,
+            poll_interval,
+            capture_images,
+            warm_up_capture,
+            keep_images_in_memory,
+            retain_saved_images,
+            image_format,
+            cursor_resolution: _,
+            normalize_size,
+            events,
+            drop_out_of_bounds,
+            move_threshold,
+            coalesce_window,
+            double_click_interval,
+            double_click_radius,
+            spill,
+            journal,
+            replay_window,
+            start_time,
+            time_offset_ms,
+            similar_cursor_distance,
+            max_cursors,
+            image_hasher,
+            record_moves,
+            grab_cursor_on_click,
+            recorded_buttons,
+            record_motion_deltas,
+            record_modifiers,
+            adaptive_polling,
+            auto_stop_after_idle,
+            filter,
+            sink,
+            prune_unused_cursors: prune,
+        } = self;
+
+        let next_cursor_id = next_free_cursor_id(&prev_cursors, next_cursor_id);
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        let pause_signal = Arc::new(AtomicBool::new(false));
+        let abort_signal = Arc::new(OnceLock::new());
+        let abandon_signal = Arc::new(AtomicBool::new(false));
+        let new_bounds = Arc::new(Mutex::new(None));
+        let (tx, rx) = oneshot::channel();
+        let (stats_tx, stats_rx) = watch::channel(CursorStats::default());
+        let recorded = Arc::new(Mutex::new(CursorActorResponse {
+            cursors: prev_cursors,
+            next_cursor_id,
+            ..Default::default()
+        }));
+
+        let actor = spawn_actor({
+            let stop_signal = stop_signal.clone();
+            let pause_signal = pause_signal.clone();
+            let abort_signal = abort_signal.clone();
+            let abandon_signal = abandon_signal.clone();
+            let new_bounds = new_bounds.clone();
+            let recorded = recorded.clone();
+            async move {
+                if capture_images && warm_up_capture {
+                    source.warm_up();
+                }
+
+                let mut last_window_refresh = None;
+                if let Some((window_bounds, _)) = &mut window_bounds {
+                    let bounds = window_bounds();
+                    if has_area(&bounds) {
+                        monitors[0] = bounds;
+                    }
+                    last_window_refresh = Some(Instant::now());
+                }
+
+                let mut last_mouse_state = source.mouse_state();
+                // process_time_ms is measured from here, minus any time spent paused
+                let start_time = start_time.unwrap_or_else(Instant::now);
+                lock(&recorded).start = CursorMarker::new(
+                    last_mouse_state.coords,
+                    source.cursor_visible(),
+                    &monitors,
+                    start_time.elapsed().as_secs_f64() * 1000.0 + time_offset_ms,
+                );
+                let mut paused_duration = Duration::ZERO;
+                let mut paused_at = None;
+                let mut coalescer = MoveCoalescer::new(move_threshold, coalesce_window);
+                let mut drags = DragTracker::default();
+                let mut motion = Motion::default();
+                let mut last_visible = true;
+                let mut last_modifiers = Vec::new();
+                // Whether the last captured cursor was a wait cursor
+                let mut busy = false;
+                let mut sample_count = 0;
+                let mut interval = poll_interval;
+                let mut idle_ticks = 0;
+                let mut last_keys = Vec::new();
+                // When anything last moved, was pressed or typed, in process time
+                let mut last_input_ms =
+                    start_time.elapsed().as_secs_f64() * 1000.0 + time_offset_ms;
+                let mut overruns = 0;
+                let mut last_overrun_warning: Option<Instant> = None;
+                let mut stuck_input = StuckInput::default();
+                let mut last_stuck_input_warning: Option<Instant> = None;
+                let mut last_stats_update = Instant::now();
+                let mut last_device_id = source.pointer_device();
+                let mut click_counter =
+                    ClickCounter::new(double_click_interval, double_click_radius);
+                // When each held button was pressed
+                let mut press_times = HashMap::new();
+
+                // Without image support every event uses the default cursor
+                let capture_images = capture_images && cfg!(feature = "cursor-images");
+                let image_capture_disabled =
+                    capture_images && !keep_images_in_memory && !ensure_cursors_dir(&cursors_dir);
+                lock(&recorded).image_capture_disabled = image_capture_disabled;
+
+                if prune {
+                    if let Err(e) = prune_unused_cursors(&cursors_dir, &lock(&recorded).cursors) {
+                        error!("Failed to prune unused cursor images: {e}");
+                    }
+                }
+
+                let journal = journal.map(|(path, interval)| EventJournal::new(path, interval));
+                let mut events = EventSink::new(events, filter, journal);
+                events.sink = sink;
+                let mut spill = spill
+                    .filter(|_| replay_window.is_none())
+                    .map(|(path, max_buffered)| EventSpill::new(path, max_buffered));
+
+                let mut saver = CursorSaver::new(SaveOptions {
+                    cursors_dir: cursors_dir.clone(),
+                    keep_images_in_memory,
+                    retain_saved_images,
+                    image_format,
+                    normalize_size,
+                    first_monitor: monitors[0],
+                });
+                // Cursors whose image is still being saved
+                let mut unsaved_cursors = HashMap::new();
+                // Images that couldn't be read or saved, which aren't retried on every tick
+                // and are recorded as the default cursor so no event points at a file that
+                // was never written
+                let mut unusable_images = HashSet::new();
+                let mut failed_cursors = HashSet::new();
+                let mut stale_cursors = HashSet::new();
+                let mut similar_cursors = similar_cursor_distance.map(SimilarCursors::new);
+                let mut last_cursor: Option<(S::Handle, String)> = None;
+                let capture_images = capture_images && !image_capture_disabled;
+                let saves_to_disk = capture_images && !keep_images_in_memory;
+
+                // Discard wheel and raw movement from before this recording started
+                source.take_scroll_delta();
+                source.take_raw_motion();
+
+                while !stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+                    if pause_signal.load(std::sync::atomic::Ordering::Relaxed) {
+                        paused_at.get_or_insert_with(Instant::now);
+
+                        // Keep the baseline current so nothing done while paused shows
+                        // up as a move, click or scroll once recording resumes
+                        source.take_scroll_delta();
+                        source.take_raw_motion();
+                        last_mouse_state = source.mouse_state();
+                        motion = Motion::default();
+                        tokio::time::sleep(interval).await;
+                        continue;
+                    }
+
+                    if let Some(paused_at) = paused_at.take() {
+                        paused_duration += paused_at.elapsed();
+                    }
+
+                    let tick_start = Instant::now();
+                    let mouse_state = source.mouse_state();
+                    sample_count += 1;
+                    let keys = source.keys();
+                    let modifiers = active_modifiers(&keys);
+
+                    // Switching devices, like picking up a pen, makes the cursor jump, which
+                    // shouldn't count towards velocity or a double-click
+                    let device_id = source.pointer_device();
+                    if device_id != last_device_id {
+                        motion = Motion::default();
+                        click_counter.break_streak();
+                        last_device_id = device_id;
+                    }
+                    let elapsed = start_time
+                        .elapsed()
+                        .saturating_sub(paused_duration)
+                        .as_secs_f64()
+                        * 1000.0
+                        + time_offset_ms;
+                    let unix_time = chrono::Utc::now().timestamp_millis() as f64;
+
+                    let mut bounds = new_bounds
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .take();
+                    if let Some((window_bounds, refresh_interval)) = &mut window_bounds {
+                        let due =
+                            last_window_refresh.is_none_or(|at| at.elapsed() >= *refresh_interval);
+                        if due {
+                            let window = window_bounds();
+                            if has_area(&window)
+                                && !same_bounds(&window, bounds.as_ref().unwrap_or(&monitors[0]))
+                            {
+                                bounds = Some(window);
+                            }
+                            last_window_refresh = Some(Instant::now());
+                        }
+                    }
+                    if let Some(bounds) = bounds {
+                        monitors[0] = bounds;
+                        saver.set_first_monitor(bounds);
+                        // Normalized positions jump with the bounds, not the cursor
+                        motion = Motion::default();
+                        lock(&recorded).bounds_changes.push(BoundsChange {
+                            process_time_ms: elapsed,
+                            bounds,
+                        });
+                    }
+
+                    if record_modifiers {
+                        for (name, _) in MODIFIERS {
+                            let down = modifiers.iter().any(|modifier| modifier == name);
+                            if down == last_modifiers.iter().any(|modifier| modifier == name) {
+                                continue;
+                            }
+
+                            let modifier_event = ModifierEvent {
+                                modifier: name.to_string(),
+                                down,
+                                process_time_ms: elapsed,
+                                unix_time_ms: unix_time,
+                            };
+                            events.record(
+                                &mut lock(&recorded),
+                                CursorEvent::Modifier(modifier_event),
+                            );
+                        }
+                        last_modifiers.clone_from(&modifiers);
+                    }
+
+                    let Some(Position { x, y, monitor }) =
+                        normalize_position(mouse_state.coords, &monitors, drop_out_of_bounds)
+                    else {
+                        // Wheel and raw movement outside the recorded area is dropped along
+                        // with it
+                        source.take_scroll_delta();
+                        source.take_raw_motion();
+                        last_mouse_state = mouse_state;
+                        motion = Motion::default();
+                        tokio::time::sleep(interval).await;
+                        continue;
+                    };
+
+                    let (velocity, acceleration) = motion.sample(x, y, monitor, elapsed);
+                    let monitor_id = monitor.map(|index| {
+                        monitor_ids
+                            .as_ref()
+                            .map_or(index, |ids| ids[index as usize])
+                    });
+
+                    // Cursors are only added to the map once their file has been written
+                    let mut saved = false;
+                    while let Some(res) = saver.try_finished() {
+                        finish_cursor_save(
+                            &mut lock(&recorded),
+                            &mut unsaved_cursors,
+                            &mut unusable_images,
+                            &mut failed_cursors,
+                            res,
+                        );
+                        saved = true;
+                    }
+                    if saved && saves_to_disk {
+                        // Once stop_timeout took the cursors, the map only has the ones
+                        // saved since, and would drop the rest from the sidecar
+                        let recorded = lock(&recorded);
+                        if !abandon_signal.load(std::sync::atomic::Ordering::Relaxed) {
+                            update_cursor_sidecar(&cursors_dir, &recorded.cursors);
+                        }
+                    }
+
+                    // Without moves, the cursor only matters for the clicks on this tick
+                    let buttons_changed =
+                        mouse_state.button_pressed != last_mouse_state.button_pressed;
+                    let capture_images = capture_images && (record_moves || buttons_changed);
+
+                    // Grabbing the image is expensive native work, so it's only redone when the
+                    // system hands out a different cursor than on the previous tick. Platforms
+                    // without a cheap cursor identity return no handle and capture every tick.
+                    let handle = if !capture_images {
+                        None
+                    } else {
+                        source.cursor_handle()
+                    };
+                    let cached_id = match (&handle, &last_cursor) {
+                        (Some(handle), Some((last_handle, id)))
+                            if handle == last_handle
+                                && !(grab_cursor_on_click && buttons_changed) =>
+                        {
+                            Some(id.clone())
+                        }
+                        _ => None,
+                    };
+
+                    let cursor_id = if let Some(id) = cached_id {
+                        id
+                    } else {
+                        let cursor_data = if !capture_images {
+                            None
+                        } else {
+                            source.cursor_image()
+                        };
+                        let captured = cursor_data.is_some();
+
+                        // A tick without an image, like a failed capture, says nothing
+                        // about whether the app is still busy
+                        if let Some(data) = &cursor_data {
+                            let now_busy = data.kind == CursorKind::Wait;
+                            if now_busy != busy {
+                                busy = now_busy;
+                                events.record(
+                                    &mut lock(&recorded),
+                                    CursorEvent::Busy(BusyStateEvent {
+                                        busy,
+                                        process_time_ms: elapsed,
+                                        unix_time_ms: unix_time,
+                                    }),
+                                );
+                            }
+                        }
+
+                        let id = if let Some(data) = cursor_data {
+                            let mut response = lock(&recorded);
+                            let hash = image_hasher(&data.image);
+
+                            // Check if we've seen this cursor data before
+                            if let Some(existing) = response.cursors.get(&hash) {
+                                existing.id.to_string()
+                            } else if unusable_images.contains(&hash) {
+                                "default".to_string()
+                            } else if let Some(id) = unsaved_cursors.get(&hash) {
+                                id.to_string()
+                            } else if let Some(id) = similar_cursors
+                                .as_mut()
+                                .and_then(|similar| similar.find(hash, &data.image))
+                            {
+                                if failed_cursors.contains(&id) {
+                                    "default".to_string()
+                                } else {
+                                    id.to_string()
+                                }
+                            } else if image_dimensions(&data.image).is_none() {
+                                warn!("Cursor image isn't in a readable format, recording it as the default cursor");
+                                unusable_images.insert(hash);
+                                "default".to_string()
+                            } else if response.cursors.len() + unsaved_cursors.len() >= max_cursors
+                            {
+                                if !response.cursor_limit_reached {
+                                    warn!("Recorded {max_cursors} distinct cursors, recording any new ones as the default cursor");
+                                    response.cursor_limit_reached = true;
+                                }
+                                // Not unusable as such, but it's looked up the same way
+                                unusable_images.insert(hash);
+                                "default".to_string()
+                            } else {
+                                // New cursor data - leave decoding and saving it to the saver so
+                                // the polling loop doesn't stall on image work or disk I/O
+                                let id = response.next_cursor_id;
+                                response.next_cursor_id += 1;
+                                if let Some(similar) = &mut similar_cursors {
+                                    similar.insert(hash, &data.image, id);
+                                }
+
+                                unsaved_cursors.insert(hash, id);
+                                saver.push(hash, id, data);
+
+                                id.to_string()
+                            }
+                        } else {
+                            "default".to_string()
+                        };
+
+                        last_cursor = handle
+                            .filter(|_| captured)
+                            .map(|handle| (handle, id.clone()));
+                        id
+                    };
+
+                    if capture_images && cursor_id != "default" && source.cursor_stale() {
+                        stale_cursors.insert(cursor_id.clone());
+                    }
+
+                    let visible = source.cursor_visible();
+                    let moved = mouse_state.coords != last_mouse_state.coords;
+                    if moved {
+                        drags.moved();
+                    }
+
+                    let delta = record_motion_deltas.then(|| match source.take_raw_motion() {
+                        Some((x, y)) => MotionDelta { x, y, raw: true },
+                        None => MotionDelta {
+                            x: (mouse_state.coords.0 - last_mouse_state.coords.0) as f64,
+                            y: (mouse_state.coords.1 - last_mouse_state.coords.1) as f64,
+                            raw: false,
+                        },
+                    });
+                    // A captured cursor stays put while the mouse moves
+                    let raw_moved =
+                        delta.is_some_and(|delta| delta.raw && (delta.x != 0.0 || delta.y != 0.0));
+
+                    // Showing or hiding the cursor is recorded as a move so the last move
+                    // before any point in time tells whether the cursor was visible
+                    if record_moves && (moved || raw_moved || visible != last_visible) {
+                        let mouse_event = CursorMoveEvent {
+                            active_modifiers: modifiers.clone(),
+                            cursor_id: cursor_id.clone(),
+                            process_time_ms: elapsed,
+                            unix_time_ms: unix_time,
+                            x,
+                            y,
+                            screen_x: mouse_state.coords.0,
+                            screen_y: mouse_state.coords.1,
+                            monitor,
+                            monitor_id,
+                            device_id,
+                            visible,
+                            off_screen: monitor.is_none(),
+                            velocity_x: velocity.x,
+                            velocity_y: velocity.y,
+                            acceleration_x: acceleration.x,
+                            acceleration_y: acceleration.y,
+                            delta,
+                        };
+                        if let Some(mouse_event) = coalescer.push(mouse_state.coords, mouse_event) {
+                            events.record(&mut lock(&recorded), CursorEvent::Move(mouse_event));
+                        }
+                    } else if let Some(mouse_event) = coalescer.flush() {
+                        events.record(&mut lock(&recorded), CursorEvent::Move(mouse_event));
+                    }
+
+                    for (num, &pressed) in mouse_state.button_pressed.iter().enumerate() {
+                        let Some(prev) = last_mouse_state.button_pressed.get(num) else {
+                            continue;
+                        };
+
+                        if pressed == *prev {
+                            continue;
+                        }
+
+                        let button = mouse_button(num as u8);
+                        if !recorded_buttons
+                            .as_ref()
+                            .is_none_or(|buttons| buttons.contains(&button))
+                        {
+                            continue;
+                        }
+
+                        // Polling only notices the press up to an interval after it happened,
+                        // by when a moving cursor has travelled on, so the position the
+                        // platform saw it at is used when there is one
+                        let press_position = pressed
+                            .then(|| source.take_press_position(num as u8))
+                            .flatten()
+                            .and_then(|coords| {
+                                normalize_position(coords, &monitors, drop_out_of_bounds)
+                                    .map(|position| (coords, position))
+                            });
+                        let (coords, Position { x, y, monitor }) = press_position
+                            .unwrap_or((mouse_state.coords, Position { x, y, monitor }));
+                        let monitor_id = monitor.map(|index| {
+                            monitor_ids
+                                .as_ref()
+                                .map_or(index, |ids| ids[index as usize])
+                        });
+
+                        let click_count = if pressed {
+                            click_counter.press(num as u8, coords, elapsed)
+                        } else {
+                            click_counter.release(num as u8, coords, elapsed)
+                        };
+                        let duration_ms = if pressed {
+                            *lock(&recorded).click_counts.entry(button).or_default() += 1;
+                            press_times.insert(num, elapsed);
+                            None
+                        } else {
+                            press_times
+                                .remove(&num)
+                                .map(|pressed_at| elapsed - pressed_at)
+                        };
+
+                        let mouse_event = CursorClickEvent {
+                            down: pressed,
+                            duration_ms,
+                            click_count,
+                            active_modifiers: modifiers.clone(),
+                            cursor_num: num as u8,
+                            button: Some(button),
+                            cursor_id: cursor_id.clone(),
+                            process_time_ms: elapsed,
+                            unix_time_ms: unix_time,
+                            x,
+                            y,
+                            screen_x: coords.0,
+                            screen_y: coords.1,
+                            monitor,
+                            monitor_id,
+                            device_id,
+                            frame: None,
+                        };
+                        events.record(&mut lock(&recorded), CursorEvent::Click(mouse_event));
+
+                        let position = XY::new(x, y);
+                        if pressed {
+                            drags.press(num as u8, position, elapsed);
+                        } else if let Some(drag) = drags.release(num as u8, position, elapsed) {
+                            events.record(&mut lock(&recorded), CursorEvent::Drag(drag));
+                        }
+                    }
+
+                    let (delta_x, delta_y) = source.take_scroll_delta();
+                    let scrolled = delta_x != 0.0 || delta_y != 0.0;
+                    if scrolled {
+                        let scroll_event = CursorScrollEvent {
+                            delta_x,
+                            delta_y,
+                            process_time_ms: elapsed,
+                            unix_time_ms: unix_time,
+                            x,
+                            y,
+                            monitor,
+                        };
+                        events.record(&mut lock(&recorded), CursorEvent::Scroll(scroll_event));
+                    }
+
+                    let presses = mouse_state
+                        .button_pressed
+                        .iter()
+                        .zip(&last_mouse_state.button_pressed)
+                        .filter(|(pressed, was_pressed)| **pressed && !**was_pressed)
+                        .count() as u32;
+                    if stuck_input.sample(mouse_state.coords, presses + scrolled as u32, elapsed) {
+                        lock(&recorded).input_possibly_stuck = true;
+
+                        let warned_recently = last_stuck_input_warning
+                            .is_some_and(|at| at.elapsed() < STUCK_INPUT_WARNING_INTERVAL);
+                        if !warned_recently {
+                            warn!("The cursor has been at {:?} for over {STUCK_INPUT_AFTER:?} while clicking or scrolling, input may be stuck or inaccessible", mouse_state.coords);
+                            last_stuck_input_warning = Some(Instant::now());
+                        }
+                    }
+
+                    if let Some((idle_interval, idle_after)) = adaptive_polling {
+                        let active = moved
+                            || raw_moved
+                            || scrolled
+                            || mouse_state.button_pressed != last_mouse_state.button_pressed;
+                        idle_ticks = if active { 0 } else { idle_ticks + 1 };
+
+                        let next = if idle_ticks >= idle_after {
+                            idle_interval
+                        } else {
+                            poll_interval
+                        };
+                        if next != interval {
+                            interval = next;
+                            lock(&recorded)
+                                .poll_interval_changes
+                                .push(PollIntervalChange {
+                                    process_time_ms: elapsed,
+                                    interval,
+                                });
+                        }
+                    }
+
+                    if let Some(idle) = auto_stop_after_idle {
+                        let active = moved
+                            || raw_moved
+                            || scrolled
+                            || mouse_state.button_pressed != last_mouse_state.button_pressed
+                            || keys != last_keys;
+                        if active {
+                            last_input_ms = elapsed;
+                        } else if elapsed - last_input_ms >= idle.as_secs_f64() * 1000.0 {
+                            info!("No input for {idle:?}, stopping the cursor recorder");
+                            lock(&recorded).auto_stopped = true;
+                            stop_signal.store(true, std::sync::atomic::Ordering::Relaxed);
+                        }
+                        last_keys = keys;
+                    }
+
+                    last_mouse_state = mouse_state;
+                    last_visible = visible;
+                    if let Some(spill) = &mut spill {
+                        spill.spill_if_full(&mut lock(&recorded));
+                    }
+                    if let Some(window) = replay_window {
+                        lock(&recorded).discard_before(elapsed - window.as_secs_f64() * 1000.0);
+                    }
+                    if let Some(journal) = &mut events.journal {
+                        journal.flush_if_due();
+                    }
+                    if last_stats_update.elapsed() >= STATS_INTERVAL {
+                        let cursors = lock(&recorded).cursors.len();
+                        publish_stats(&stats_tx, events.stats(sample_count, overruns, cursors));
+                        last_stats_update = Instant::now();
+                    }
+
+                    let tick_duration = tick_start.elapsed();
+                    if tick_duration > interval * OVERRUN_FACTOR {
+                        overruns += 1;
+                        lock(&recorded).overruns = overruns;
+
+                        let warned_recently = last_overrun_warning
+                            .is_some_and(|at| at.elapsed() < OVERRUN_WARNING_INTERVAL);
+                        if !warned_recently {
+                            warn!("Sampling the cursor took {tick_duration:?}, more than {OVERRUN_FACTOR}x the {interval:?} poll interval ({overruns} overruns so far)");
+                            last_overrun_warning = Some(Instant::now());
+                        }
+                    }
+
+                    tokio::time::sleep(interval).await;
+                }
+
+                if let Some(&delete_cursor_files) = abort_signal.get() {
+                    // Saves still running would write their files after they're deleted
+                    saver.discard_queued();
+                    while let Some(res) = saver.finished().await {
+                        finish_cursor_save(
+                            &mut lock(&recorded),
+                            &mut unsaved_cursors,
+                            &mut unusable_images,
+                            &mut failed_cursors,
+                            res,
+                        );
+                    }
+
+                    let discarded = std::mem::take(&mut *lock(&recorded));
+                    if delete_cursor_files {
+                        let (new_cursors, kept_cursors): (Cursors, Cursors) = discarded
+                            .cursors
+                            .into_iter()
+                            .partition(|(_, cursor)| cursor.id >= next_cursor_id);
+                        remove_cursor_files(&cursors_dir, new_cursors.values());
+                        if saves_to_disk {
+                            update_cursor_sidecar(&cursors_dir, &kept_cursors);
+                        }
+                    }
+
+                    if let Some(spill) = spill {
+                        spill.discard();
+                    }
+                    if let Some(journal) = events.journal.take() {
+                        journal.finish();
+                    }
+                    return CursorActorResponse::default();
+                }
+
+                if let Some(mouse_event) = coalescer.flush() {
+                    events.record(&mut lock(&recorded), CursorEvent::Move(mouse_event));
+                }
+                lock(&recorded).dropped_stream_events = events.dropped;
+
+                // Stopping while paused doesn't count the pause towards the end time
+                let paused_duration =
+                    paused_duration + paused_at.map_or(Duration::ZERO, |at| at.elapsed());
+                let duration = start_time.elapsed().saturating_sub(paused_duration);
+                {
+                    let mut recorded = lock(&recorded);
+                    recorded.end = CursorMarker::new(
+                        source.mouse_state().coords,
+                        source.cursor_visible(),
+                        &monitors,
+                        duration.as_secs_f64() * 1000.0 + time_offset_ms,
+                    );
+                    recorded.duration = duration;
+                    recorded.sample_count = sample_count;
+                }
+
+                // Every cursor in the response should have its file on disk
+                while let Some(res) = saver.finished().await {
+                    finish_cursor_save(
+                        &mut lock(&recorded),
+                        &mut unsaved_cursors,
+                        &mut unusable_images,
+                        &mut failed_cursors,
+                        res,
+                    );
+                }
+
+                {
+                    // Held until the response is complete, so stop_timeout either takes
+                    // all of it or sees nothing of the wrap up
+                    let mut recorded = lock(&recorded);
+                    if abandon_signal.load(std::sync::atomic::Ordering::Relaxed) {
+                        // Nobody reads what's restored from here on, so the files are left
+                        // for recovering the events that weren't handed out, and the
+                        // sidecar keeps listing the cursors that were
+                        if let Some(journal) = events.journal.take() {
+                            journal.keep();
+                        }
+                        return CursorActorResponse::default();
+                    }
+
+                    if let Some(spill) = spill {
+                        spill.restore(&mut recorded);
+                    }
+
+                    // Events recorded while a save was still running point at its cursor
+                    forget_failed_cursors(&mut recorded, &failed_cursors);
+
+                    for cursor in recorded.cursors.values_mut() {
+                        cursor.possibly_stale |= stale_cursors.contains(&cursor.id.to_string());
+                    }
+                    if saves_to_disk {
+                        update_cursor_sidecar(&cursors_dir, &recorded.cursors);
+                    }
+                }
+
+                if let Some(journal) = events.journal.take() {
+                    journal.finish();
+                }
+                let cursors = lock(&recorded).cursors.len();
+                let summary = events.stats(sample_count, overruns, cursors);
+                if let Some(sink) = &mut events.sink {
+                    sink.on_stop(&summary);
+                }
+                publish_stats(&stats_tx, summary);
+                std::mem::take(&mut *lock(&recorded))
+            }
+        });
+
+        // A panic in a native call or a source would otherwise just drop the channel,
+        // which looks the same as the runtime shutting down
+        spawn_actor(async move {
+            match actor.await {
+                Ok(response) => {
+                    tx.send(Ok(response)).ok();
+                }
+                Err(e) if e.is_panic() => {
+                    tx.send(Err(panicked(e.into_panic()))).ok();
+                }
+                Err(_) => {}
+            }
+        });
+
+        Ok(CursorActor {
+            rx,
+            stop_signal,
+            pause_signal,
+            abort_signal,
+            abandon_signal,
+            new_bounds,
+            recorded,
+            stats: stats_rx,
+        })
+    }
+}
+
+struct Position {
+    x: f64,
+    y: f64,
+    monitor: Option<u32>,
+}
+
+/// Logs a panic that took down the recorder, and turns it into the error reported for it.
+fn panicked(panic: Box<dyn std::any::Any + Send>) -> CursorRecordError {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    error!("Cursor recorder panicked: {message}");
+    CursorRecordError::Panicked(message)
+}
+
+/// Why a blocking recorder's thread went away without sending a response.
+fn join_stopped(thread: std::thread::JoinHandle<()>) -> CursorRecordError {
+    match thread.join() {
+        Ok(()) => CursorRecordError::ThreadStopped,
+        Err(panic) => panicked(panic),
+    }
+}
+
+/// Hands out the events recorded so far in place of a response the recorder didn't send
+/// in time, and tells it to leave the spill file and journal for whoever recovers them.
+fn abandon(
+    recorded: &Mutex<CursorActorResponse>,
+    abandon_signal: &AtomicBool,
+) -> CursorActorResponse {
+    let mut recorded = lock(recorded);
+    abandon_signal.store(true, std::sync::atomic::Ordering::Relaxed);
+    std::mem::take(&mut *recorded)
+}
+
+fn set_new_bounds(
+    new_bounds: &Mutex<Option<Bounds>>,
+    bounds: Bounds,
+) -> Result<(), CursorRecordError> {
+    if !has_area(&bounds) {
+        return Err(CursorRecordError::DegenerateMonitor {
+            index: 0,
+            width: bounds.width,
+            height: bounds.height,
+        });
+    }
+
+    *new_bounds.lock().unwrap_or_else(PoisonError::into_inner) = Some(bounds);
+    Ok(())
+}
+
+fn has_area(bounds: &Bounds) -> bool {
+    bounds.width.is_finite()
+        && bounds.height.is_finite()
+        && bounds.width > 0.0
+        && bounds.height > 0.0
+}
+
+fn same_bounds(a: &Bounds, b: &Bounds) -> bool {
+    (a.x, a.y, a.width, a.height) == (b.x, b.y, b.width, b.height)
+}
+
+/// Maps screen coordinates into the `0.0..=1.0` range of the monitor they fall on. The
+/// first monitor wins where monitors overlap. Positions outside every monitor are
+/// clamped to the first one, or `None` if `drop_out_of_bounds` is set.
+fn normalize_position(
+    coords: (i32, i32),
+    monitors: &[Bounds],
+    drop_out_of_bounds: bool,
+) -> Option<Position> {
+    let normalize = |bounds: &Bounds| {
+        (
+            (coords.0 as f64 - bounds.x) / bounds.width,
+            (coords.1 as f64 - bounds.y) / bounds.height,
+        )
+    };
+
+    let range = 0.0..=1.0;
+    for (index, bounds) in monitors.iter().enumerate() {
+        let (x, y) = normalize(bounds);
+        if range.contains(&x) && range.contains(&y) {
+            return Some(Position {
+                x,
+                y,
+                monitor: Some(index as u32),
+            });
+        }
+    }
+
+    if drop_out_of_bounds {
+        return None;
+    }
+
+    let (x, y) = normalize(monitors.first()?);
+    Some(Position {
+        x: x.clamp(0.0, 1.0),
+        y: y.clamp(0.0, 1.0),
+        monitor: None,
+    })
+}
+
+/// Drops moves that barely differ from the last recorded one. The latest dropped move
+/// is held back and flushed once the cursor stops, so the end of a gesture isn't lost.
+struct MoveCoalescer {
+    threshold: f64,
+    window_ms: f64,
+    last_recorded: Option<((i32, i32), f64, bool)>,
+    held: Option<((i32, i32), CursorMoveEvent)>,
+}
+
+impl MoveCoalescer {
+    fn new(threshold: f64, window: Duration) -> Self {
+        Self {
+            threshold,
+            window_ms: window.as_secs_f64() * 1000.0,
+            last_recorded: None,
+            held: None,
+        }
+    }
+
+    /// Returns the move if it should be recorded.
+    fn push(&mut self, coords: (i32, i32), mut event: CursorMoveEvent) -> Option<CursorMoveEvent> {
+        // The motion of a held back move is carried by whichever move replaces it
+        if let Some((_, held)) = self.held.take() {
+            if let (Some(delta), Some(held)) = (&mut event.delta, held.delta) {
+                delta.x += held.x;
+                delta.y += held.y;
+            }
+        }
+
+        if let Some((last_coords, last_time, last_visible)) = self.last_recorded {
+            let distance =
+                ((coords.0 - last_coords.0) as f64).hypot((coords.1 - last_coords.1) as f64);
+
+            // Visibility changes are never coalesced away
+            if distance < self.threshold
+                && event.process_time_ms - last_time < self.window_ms
+                && event.visible == last_visible
+            {
+                self.held = Some((coords, event));
+                return None;
+            }
+        }
+
+        self.last_recorded = Some((coords, event.process_time_ms, event.visible));
+        Some(event)
+    }
+
+    /// Returns the held back move, if any. Called when the cursor didn't move this tick.
+    fn flush(&mut self) -> Option<CursorMoveEvent> {
+        let (coords, event) = self.held.take()?;
+        self.last_recorded = Some((coords, event.process_time_ms, event.visible));
+        Some(event)
+    }
+}
+
+struct Sample {
+    x: f64,
+    y: f64,
+    monitor: Option<u32>,
+    time_ms: f64,
+    velocity: XY<f64>,
+}
+
+/// Tracks velocity and acceleration between consecutive samples.
+#[derive(Default)]
+struct Motion {
+    last: Option<Sample>,
+}
+
+impl Motion {
+    /// Returns the velocity and acceleration since the previous sample. Both are zero
+    /// for the first sample, when no time has passed, or when the cursor moved between
+    /// monitors, whose normalized coordinates can't be compared.
+    fn sample(&mut self, x: f64, y: f64, monitor: Option<u32>, time_ms: f64) -> (XY<f64>, XY<f64>) {
+        let zero = XY::new(0.0, 0.0);
+        let (velocity, acceleration) = match &self.last {
+            Some(last) if last.monitor == monitor && time_ms > last.time_ms => {
+                let dt = (time_ms - last.time_ms) / 1000.0;
+                let velocity = XY::new((x - last.x) / dt, (y - last.y) / dt);
+                let acceleration = XY::new(
+                    (velocity.x - last.velocity.x) / dt,
+                    (velocity.y - last.velocity.y) / dt,
+                );
+                (velocity, acceleration)
+            }
+            _ => (zero, zero),
+        };
+
+        self.last = Some(Sample {
+            x,
+            y,
+            monitor,
+            time_ms,
+            velocity,
+        });
+        (velocity, acceleration)
+    }
+}
+
+struct Release {
+    coords: (i32, i32),
+    time_ms: f64,
+    click_count: u8,
+}
+
+/// Counts consecutive clicks of the same button that land close together in time and
+/// space, the way the OS decides what's a double-click.
+struct ClickCounter {
+    interval_ms: f64,
+    radius: f64,
+    pressed: HashMap<u8, u8>,
+    released: HashMap<u8, Release>,
+}
+
+impl ClickCounter {
+    fn new(interval: Duration, radius: f64) -> Self {
+        Self {
+            interval_ms: interval.as_secs_f64() * 1000.0,
+            radius,
+            pressed: HashMap::new(),
+            released: HashMap::new(),
+        }
+    }
+
+    fn press(&mut self, cursor_num: u8, coords: (i32, i32), time_ms: f64) -> u8 {
+        let click_count = match self.released.get(&cursor_num) {
+            Some(release)
+                if time_ms - release.time_ms <= self.interval_ms
+                    && ((coords.0 - release.coords.0) as f64)
+                        .hypot((coords.1 - release.coords.1) as f64)
+                        <= self.radius =>
+            {
+                release.click_count.saturating_add(1)
+            }
+            _ => 1,
+        };
+
+        self.pressed.insert(cursor_num, click_count);
+        click_count
+    }
+
+    fn release(&mut self, cursor_num: u8, coords: (i32, i32), time_ms: f64) -> u8 {
+        // A release without a recorded press (eg. held since before recording) is a
+        // click of its own
+        let click_count = self.pressed.remove(&cursor_num).unwrap_or(1);
+
+        self.released.insert(
+            cursor_num,
+            Release {
+                coords,
+                time_ms,
+                click_count,
+            },
+        );
+        click_count
+    }
+
+    /// Makes the next press a single click, whatever came before it.
+    fn break_streak(&mut self) {
+        self.released.clear();
+    }
+}
+
+/// Watches for the position staying exactly the same while buttons or the wheel are
+/// used, see [`CursorActorResponse::input_possibly_stuck`].
+#[derive(Default)]
+struct StuckInput {
+    // Where the position has been since when
+    still: Option<((i32, i32), f64)>,
+    actions: u32,
+}
+
+impl StuckInput {
+    /// Takes the clicks and scrolls since the previous sample, and returns whether input
+    /// looks stuck.
+    fn sample(&mut self, coords: (i32, i32), actions: u32, time_ms: f64) -> bool {
+        let since_ms = match self.still {
+            Some((still_at, since_ms)) if still_at == coords => {
+                self.actions += actions;
+                since_ms
+            }
+            _ => {
+                self.still = Some((coords, time_ms));
+                self.actions = 0;
+                time_ms
+            }
+        };
+
+        self.actions >= STUCK_INPUT_ACTIONS
+            && time_ms - since_ms >= STUCK_INPUT_AFTER.as_secs_f64() * 1000.0
+    }
+}
+
+struct Press {
+    start: XY<f64>,
+    start_time_ms: f64,
+    moved: bool,
+}
+
+/// Pairs up presses and releases of the same button that had movement in between.
+#[derive(Default)]
+struct DragTracker {
+    pressed: HashMap<u8, Press>,
+}
+
+impl DragTracker {
+    fn press(&mut self, cursor_num: u8, start: XY<f64>, start_time_ms: f64) {
+        self.pressed.insert(
+            cursor_num,
+            Press {
+                start,
+                start_time_ms,
+                moved: false,
+            },
+        );
+    }
+
+    /// Marks every currently held button as dragged.
+    fn moved(&mut self) {
+        for press in self.pressed.values_mut() {
+            press.moved = true;
+        }
+    }
+
+    fn release(
+        &mut self,
+        cursor_num: u8,
+        end: XY<f64>,
+        end_time_ms: f64,
+    ) -> Option<CursorDragEvent> {
+        let press = self.pressed.remove(&cursor_num)?;
+        if !press.moved {
+            return None;
+        }
+
+        Some(CursorDragEvent {
+            cursor_num,
+            start: press.start,
+            end,
+            start_process_time_ms: press.start_time_ms,
+            end_process_time_ms: end_time_ms,
+        })
+    }
+}
+
+fn next_free_cursor_id(cursors: &Cursors, next_cursor_id: u32) -> u32 {
+    match cursors.values().map(|cursor| cursor.id).max() {
+        Some(max_id) if max_id >= next_cursor_id => {
+            warn!(
+                "Next cursor id {next_cursor_id} is already taken by a previous cursor, continuing from {}",
+                max_id + 1
+            );
+            max_id + 1
+        }
+        _ => next_cursor_id,
+    }
+}
+
+/// Names a `device_query` button index, which follows the OS's own numbering. Index 0 is
+/// never pressed, and 1 is always the left button, but after that:
+///
+/// - Windows: 2 right, 3 middle, 4 back and 5 forward.
+/// - macOS: 2 right and 3 middle. Other buttons aren't reported.
+/// - X11: 2 middle and 3 right, while 4 and 5 are the wheel, which is recorded as scrolls.
+///   Back and forward are 8 and 9, which aren't reported.
+fn mouse_button(index: u8) -> MouseButton {
+    match index {
+        1 => MouseButton::Left,
+        #[cfg(target_os = "linux")]
+        2 => MouseButton::Middle,
+        #[cfg(target_os = "linux")]
+        3 => MouseButton::Right,
+        #[cfg(not(target_os = "linux"))]
+        2 => MouseButton::Right,
+        #[cfg(not(target_os = "linux"))]
+        3 => MouseButton::Middle,
+        #[cfg(windows)]
+        4 => MouseButton::Back,
+        #[cfg(windows)]
+        5 => MouseButton::Forward,
+        index => MouseButton::Other(index),
+    }
+}
+
+type EventFilter = Box<dyn Fn(&CursorEvent) -> bool + Send>;
+type ImageHasher = Box<dyn Fn(&[u8]) -> u64 + Send>;
+type WindowBounds = Box<dyn FnMut() -> Bounds + Send>;
+
+/// Where recorded events go: the response or [`CursorRecorderConfig::sink`], the live
+/// stream set with [`CursorRecorderConfig::events`], and the
+/// [`CursorRecorderConfig::journal`].
+struct EventSink {
+    sender: Option<mpsc::Sender<CursorEvent>>,
+    dropped: u64,
+    filter: Option<EventFilter>,
+    journal: Option<EventJournal>,
+    sink: Option<Box<dyn CursorEventSink + Send>>,
+    moves: u64,
+    clicks: u64,
+}
+
+impl EventSink {
+    fn new(
+        sender: Option<mpsc::Sender<CursorEvent>>,
+        filter: Option<EventFilter>,
+        journal: Option<EventJournal>,
+    ) -> Self {
+        Self {
+            sender,
+            dropped: 0,
+            filter,
+            journal,
+            sink: None,
+            moves: 0,
+            clicks: 0,
+        }
+    }
+
+    fn stats(&self, samples: u64, overruns: u64, cursors: usize) -> CursorStats {
+        CursorStats {
+            samples,
+            moves: self.moves,
+            clicks: self.clicks,
+            cursors: cursors as u64,
+            overruns,
+        }
+    }
+
+    /// Buffers `event` in `response`, or hands it to the sink, and streams it, unless the
+    /// filter drops it.
+    fn record(&mut self, response: &mut CursorActorResponse, event: CursorEvent) {
+        if self.filter.as_ref().is_some_and(|filter| !filter(&event)) {
+            return;
+        }
+
+        if self.sender.is_some() {
+            self.emit(event.clone());
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.push(&event);
+        }
+
+        let sink: &mut dyn CursorEventSink = match &mut self.sink {
+            Some(sink) => sink.as_mut(),
+            None => response,
+        };
+        match event {
+            CursorEvent::Move(event) => {
+                self.moves += 1;
+                sink.on_move(event);
+            }
+            CursorEvent::Click(event) => {
+                self.clicks += 1;
+                sink.on_click(event);
+            }
+            CursorEvent::Scroll(event) => sink.on_scroll(event),
+            CursorEvent::Drag(event) => sink.on_drag(event),
+            CursorEvent::Modifier(event) => sink.on_modifier(event),
+            CursorEvent::Busy(event) => sink.on_busy(event),
+        }
+    }
+
+    fn emit(&mut self, event: CursorEvent) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        match sender.try_send(event) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                debug!("Cursor event receiver is lagging, dropping event");
+                self.dropped += 1;
+            }
+            // Nobody is listening anymore, so stop cloning events for the stream
+            Err(mpsc::error::TrySendError::Closed(_)) => self.sender = None,
+        }
+    }
+}
+
+/// Only wakes up receivers when a count changed.
+fn publish_stats(stats: &watch::Sender<CursorStats>, new: CursorStats) {
+    stats.send_if_modified(|current| {
+        let changed = *current != new;
+        *current = new;
+        changed
+    });
+}
+
+/// Image pixels are turned into points with the cursor's scale, then into the units
+/// mouse positions are in, which `bounds` shares.
+fn screen_size(resolution: XY<u32>, scale: f64, position_scale: f64, bounds: &Bounds) -> XY<f64> {
+    let units_per_pixel = position_scale / scale;
+    XY::new(
+        resolution.x as f64 * units_per_pixel / bounds.width,
+        resolution.y as f64 * units_per_pixel / bounds.height,
+    )
+}
+
+/// The backends all derive the normalized hotspot from whole pixels, so rounding gets
+/// those pixels back exactly.
+fn hotspot_pixels(hotspot: XY<f64>, resolution: XY<u32>) -> XY<u32> {
+    XY::new(
+        (hotspot.x * resolution.x as f64).round() as u32,
+        (hotspot.y * resolution.y as f64).round() as u32,
+    )
+}
+
+fn lock(response: &Mutex<CursorActorResponse>) -> MutexGuard<'_, CursorActorResponse> {
+    response.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn finish_cursor_save(
+    response: &mut CursorActorResponse,
+    unsaved_cursors: &mut HashMap<u64, u32>,
+    unusable_images: &mut HashSet<u64>,
+    failed_cursors: &mut HashSet<u32>,
+    res: Result<CursorSave, JoinError>,
+) {
+    let CursorSave {
+        hash,
+        cursor,
+        image,
+        res,
+    } = match res {
+        Ok(save) => save,
+        Err(e) => {
+            error!("Cursor image save task failed: {e}");
+            return;
+        }
+    };
+
+    let id = cursor.id;
+    match res {
+        Ok((width, height)) => {
+            if width == 0 || height == 0 {
+                warn!("Cursor {id} image has zero size ({width}x{height})");
+            }
+
+            if let Some(file_name) = &cursor.file_name {
+                debug!("Saved cursor {id} image ({width}x{height}) to: {file_name:?}");
+                response.cursor_files_written += 1;
+            }
+            if let Some(image) = image {
+                response.cursor_images.insert(id, image);
+            }
+
+            unsaved_cursors.remove(&hash);
+            response.cursors.insert(hash, cursor);
+        }
+        Err(e) => {
+            error!("Failed to save cursor {id} image, recording it as the default cursor: {e}");
+            unsaved_cursors.remove(&hash);
+            unusable_images.insert(hash);
+            failed_cursors.insert(id);
+        }
+    }
+}
+
+/// Points events at cursors whose image failed to save at the default cursor instead.
+fn forget_failed_cursors(response: &mut CursorActorResponse, failed_cursors: &HashSet<u32>) {
+    if failed_cursors.is_empty() {
+        return;
+    }
+
+    let failed: HashSet<String> = failed_cursors.iter().map(u32::to_string).collect();
+    let cursor_ids = response
+        .moves
+        .iter_mut()
+        .map(|m| &mut m.cursor_id)
+        .chain(response.clicks.iter_mut().map(|c| &mut c.cursor_id));
+    for cursor_id in cursor_ids {
+        if failed.contains(cursor_id) {
+            *cursor_id = "default".to_string();
+        }
+    }
+}
+
+/// Writes and removes a file in `cursors_dir`, as being able to create a directory
+/// doesn't mean files can be written into it.
+fn check_cursors_dir(cursors_dir: &Path) -> Result<(), CursorRecordError> {
+    let probe = cursors_dir.join(".preflight");
+    std::fs::create_dir_all(cursors_dir)
+        .and_then(|()| std::fs::write(&probe, []))
+        .and_then(|()| std::fs::remove_file(&probe))
+        .map_err(|source| CursorRecordError::CursorsDirUnwritable {
+            path: cursors_dir.to_path_buf(),
+            source,
+        })
+}
+
+fn ensure_cursors_dir(cursors_dir: &Path) -> bool {
+    match std::fs::create_dir_all(cursors_dir) {
+        Ok(()) => true,
+        Err(e) => {
+            error!(
+                "Failed to create cursors directory {}, cursor images will not be recorded: {e}",
+                cursors_dir.display()
+            );
+            false
+        }
+    }
+}
+
+/// Everything the recorder samples on each tick. [`SystemCursorSource`] reads the real
+/// mouse, keyboard and cursor, while tests can script a sequence of states instead.
+pub trait CursorSource: Send + 'static {
+    /// Cheap identity of the current cursor, see [`CursorSource::cursor_handle`].
+    type Handle: PartialEq + Send;
+
+    /// Called once per tick, before any of the other methods.
+    fn mouse_state(&mut self) -> MouseState;
+
+    fn keys(&mut self) -> Vec<Keycode>;
+
+    /// Compared between ticks so the image is only fetched when the cursor changes.
+    /// `None` means there's no cheap identity and the image is fetched every tick.
+    fn cursor_handle(&mut self) -> Option<Self::Handle>;
+
+    fn cursor_image(&mut self) -> Option<CursorData>;
+
+    fn cursor_visible(&mut self) -> bool;
+
+    /// Wheel movement since the previous call, in lines.
+    fn take_scroll_delta(&mut self) -> (f64, f64);
+
+    /// Device that produced the latest pointer input, see [`CursorMoveEvent::device_id`].
+    fn pointer_device(&mut self) -> u32;
+
+    /// Whether the cursor from [`CursorSource::cursor_handle`] may not be the one on
+    /// screen. On macOS, `NSCursor.currentSystemCursor` can keep returning a cached
+    /// cursor after another app changed it, which shows up as the system's cursor seed
+    /// moving on while the same cursor is returned. Apps setting the cursor they already
+    /// had look the same, so this only means possibly. Always false elsewhere.
+    fn cursor_stale(&mut self) -> bool;
+
+    /// Where `button` last went down, for platforms whose input listener sees the press
+    /// itself rather than the button state at the next poll. Called when a tick finds
+    /// the button newly pressed, and consumes the position.
+    fn take_press_position(&mut self, button: u8) -> Option<(i32, i32)>;
+
+    /// Relative motion from the platform's raw input since the previous call, see
+    /// [`CursorRecorderConfig::record_motion_deltas`]. `None` where raw input isn't
+    /// available, rather than no motion.
+    fn take_raw_motion(&mut self) -> Option<(f64, f64)>;
+
+    /// Called once before the first sample when
+    /// [`CursorRecorderConfig::warm_up_capture`] is on, so one-off setup like loading
+    /// native classes happens before recording rather than on its first ticks.
+    fn warm_up(&mut self) {}
+}
+
+/// The OS mouse, keyboard and cursor.
+#[derive(Default)]
+pub struct SystemCursorSource {
+    // Only Windows can pick the size cursors are captured at
+    #[cfg_attr(not(all(windows, feature = "cursor-images")), allow(dead_code))]
+    cursor_resolution: Option<u32>,
+}
+
+impl CursorSource for SystemCursorSource {
+    type Handle = CursorHandle;
+
+    fn mouse_state(&mut self) -> MouseState {
+        DEVICE_STATE.with(|state| state.get_mouse())
+    }
+
+    fn keys(&mut self) -> Vec<Keycode> {
+        DEVICE_STATE.with(|state| state.get_keys())
+    }
+
+    fn cursor_handle(&mut self) -> Option<CursorHandle> {
+        current_cursor_handle()
+    }
+
+    fn cursor_image(&mut self) -> Option<CursorData> {
+        #[cfg(all(windows, feature = "cursor-images"))]
+        if let Some(size) = self.cursor_resolution {
+            return platform_impl::get_cursor_image_data_at(size);
+        }
+
+        #[cfg(feature = "cursor-images")]
+        return get_cursor_image_data();
+
+        #[cfg(not(feature = "cursor-images"))]
+        None
+    }
+
+    fn cursor_visible(&mut self) -> bool {
+        cursor_visible()
+    }
+
+    fn take_scroll_delta(&mut self) -> (f64, f64) {
+        take_scroll_delta()
+    }
+
+    fn pointer_device(&mut self) -> u32 {
+        pointer_device()
+    }
+
+    fn cursor_stale(&mut self) -> bool {
+        cursor_possibly_stale()
+    }
+
+    fn take_press_position(&mut self, button: u8) -> Option<(i32, i32)> {
+        take_press_position(button)
+    }
+
+    fn take_raw_motion(&mut self) -> Option<(f64, f64)> {
+        take_raw_motion()
+    }
+
+    fn warm_up(&mut self) {
+        // A throwaway capture loads AppKit's cursor classes on macOS, opens the XFixes
+        // connection on X11 and creates the memory DC that Windows reuses from then on
+        self.cursor_handle();
+        #[cfg(feature = "cursor-images")]
+        self.cursor_image();
+    }
+}
+
+/// Frees what the platform kept around between captures once recording is done.
+#[cfg(all(windows, feature = "cursor-images"))]
+impl Drop for SystemCursorSource {
+    fn drop(&mut self) {
+        platform_impl::release_capture_resources();
+    }
+}
+
+thread_local! {
+    // On Linux `DeviceState` holds an `Rc` to its X connection, so it can't live
+    // inside the actor's future across await points
+    static DEVICE_STATE: DeviceState = DeviceState::new();
+}
+
+const MODIFIERS: &[(&str, &[Keycode])] = &[
+    ("Ctrl", &[Keycode::LControl, Keycode::RControl]),
+    (
+        "Alt",
+        &[
+            Keycode::LAlt,
+            Keycode::RAlt,
+            Keycode::LOption,
+            Keycode::ROption,
+        ],
+    ),
+    ("Shift", &[Keycode::LShift, Keycode::RShift]),
+    ("Meta", &[Keycode::LMeta, Keycode::RMeta, Keycode::Command]),
+];
+
+/// Collapses the pressed keys into the modifier names stored on cursor events,
+/// ignoring which side of the keyboard the modifier was pressed on.
+fn active_modifiers(keys: &[Keycode]) -> Vec<String> {
+    MODIFIERS
+        .iter()
+        .filter(|(_, codes)| codes.iter().any(|code| keys.contains(code)))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct CursorData {
+    /// PNG encoded image.
+    pub image: Vec<u8>,
+    /// Position of the click point as a fraction of the image size.
+    pub hotspot: XY<f64>,
+    pub kind: CursorKind,
+    /// The OS's name for the cursor, like `resizeLeftRight`, when it's one of the
+    /// system's standard cursors. Only macOS reports these. It's metadata for things
+    /// like tooltips, cursors are still told apart by their image.
+    pub system_name: Option<String>,
+    /// Image pixels per logical point.
+    pub scale: f64,
+    /// Frames after the first of an animated cursor, PNG encoded. Platforms that can't
+    /// extract frames leave this empty, and each frame seen is recorded as its own cursor.
+    pub frames: Vec<Vec<u8>>,
+    pub frame_duration_ms: f64,
+}
+
+static SCROLL_DELTA: Mutex<(f64, f64)> = Mutex::new((0.0, 0.0));
+static INPUT_LISTENER: Once = Once::new();
+
+/// Called by the platform scroll listener with wheel movement in lines.
+#[cfg_attr(
+    not(any(
+        target_os = "macos",
+        windows,
+        all(target_os = "linux", feature = "x11")
+    )),
+    allow(dead_code)
+)]
+fn accumulate_scroll(delta_x: f64, delta_y: f64) {
+    let mut delta = SCROLL_DELTA.lock().unwrap_or_else(PoisonError::into_inner);
+    delta.0 += delta_x;
+    delta.1 += delta_y;
+}
+
+/// Returns the wheel movement since the previous call. The platform listener is
+/// started on first use and stays installed for the lifetime of the process.
+fn take_scroll_delta() -> (f64, f64) {
+    INPUT_LISTENER.call_once(spawn_input_listener);
+    std::mem::take(&mut *SCROLL_DELTA.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+// Indexed like `MouseState::button_pressed`
+static PRESS_POSITIONS: Mutex<[Option<(i32, i32)>; 6]> = Mutex::new([None; 6]);
+
+/// Called by the platform input listener when a button goes down, with the position
+/// the system attached to the press.
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+fn set_press_position(button: u8, coords: (i32, i32)) {
+    let mut positions = PRESS_POSITIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(position) = positions.get_mut(button as usize) {
+        *position = Some(coords);
+    }
+}
+
+fn take_press_position(button: u8) -> Option<(i32, i32)> {
+    INPUT_LISTENER.call_once(spawn_input_listener);
+    PRESS_POSITIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_mut(button as usize)?
+        .take()
+}
+
+// `None` until the platform listener has raw input to report
+static RAW_MOTION: Mutex<Option<(f64, f64)>> = Mutex::new(None);
+
+/// Called by the platform input listener once it receives raw input.
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+fn enable_raw_motion() {
+    RAW_MOTION
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_or_insert((0.0, 0.0));
+}
+
+/// Called by the platform input listener with relative mouse motion.
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+fn accumulate_raw_motion(delta_x: f64, delta_y: f64) {
+    if let Some(motion) = RAW_MOTION
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_mut()
+    {
+        motion.0 += delta_x;
+        motion.1 += delta_y;
+    }
+}
+
+fn take_raw_motion() -> Option<(f64, f64)> {
+    INPUT_LISTENER.call_once(spawn_input_listener);
+    RAW_MOTION
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_mut()
+        .map(std::mem::take)
+}
+
+static POINTER_DEVICE: AtomicU32 = AtomicU32::new(0);
+
+/// Called by the platform input listener with the device behind the latest pointer
+/// input, see [`CursorMoveEvent::device_id`].
+#[cfg_attr(
+    not(any(
+        target_os = "macos",
+        windows,
+        all(target_os = "linux", feature = "x11")
+    )),
+    allow(dead_code)
+)]
+fn set_pointer_device(device_id: u32) {
+    POINTER_DEVICE.store(device_id, std::sync::atomic::Ordering::Relaxed);
+}
+
+fn pointer_device() -> u32 {
+    INPUT_LISTENER.call_once(spawn_input_listener);
+    POINTER_DEVICE.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    #[cfg(feature = "cursor-images")]
+    use image::GenericImageView;
+
+    #[cfg(feature = "cursor-images")]
+    use std::sync::atomic::AtomicUsize;
+    #[cfg(feature = "cursor-images")]
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[cfg(feature = "cursor-images")]
+    use super::images::{encode_png, save_cursor_image};
+    use super::*;
+
+    const TEST_BOUNDS: Bounds = Bounds {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 100.0,
+    };
+
+    // Images are never captured without the feature, so most image options go unused
+    #[derive(Clone)]
+    #[cfg_attr(not(feature = "cursor-images"), allow(dead_code))]
+    struct Tick {
+        coords: (i32, i32),
+        // Indexed like device_query, where 0 is unused and 1 is the left button
+        buttons: [bool; 4],
+        keys: Vec<Keycode>,
+        image: Option<u8>,
+        hotspot: XY<f64>,
+        kind: CursorKind,
+        speck: bool,
+        truncate: Option<usize>,
+        frames: usize,
+        visible: bool,
+        scroll: (f64, f64),
+        hang: Option<Duration>,
+        device_id: u32,
+        stale: bool,
+        press_position: Option<(i32, i32)>,
+        raw_motion: Option<(f64, f64)>,
+        panic: bool,
+        handle: Option<u8>,
+    }
+
+    #[cfg_attr(not(feature = "cursor-images"), allow(dead_code))]
+    impl Tick {
+        fn at(x: i32, y: i32) -> Self {
+            Self {
+                coords: (x, y),
+                buttons: [false; 4],
+                keys: vec![],
+                image: None,
+                hotspot: XY::new(0.0, 0.0),
+                kind: CursorKind::Custom,
+                speck: false,
+                truncate: None,
+                frames: 0,
+                visible: true,
+                scroll: (0.0, 0.0),
+                hang: None,
+                device_id: 0,
+                stale: false,
+                press_position: None,
+                raw_motion: None,
+                panic: false,
+                handle: None,
+            }
+        }
+
+        fn pressed(mut self, button: usize) -> Self {
+            self.buttons[button] = true;
+            self
+        }
+
+        fn keys(mut self, keys: &[Keycode]) -> Self {
+            self.keys = keys.to_vec();
+            self
+        }
+
+        /// Solid image of the given shade, so different shades are different cursors.
+        fn image(mut self, shade: u8) -> Self {
+            self.image = Some(shade);
+            self
+        }
+
+        fn hotspot(mut self, x: f64, y: f64) -> Self {
+            self.hotspot = XY::new(x, y);
+            self
+        }
+
+        fn kind(mut self, kind: CursorKind) -> Self {
+            self.kind = kind;
+            self
+        }
+
+        /// Changes a single pixel of the image, like antialiasing differences would.
+        fn speck(mut self) -> Self {
+            self.speck = true;
+            self
+        }
+
+        /// Cuts the encoded image down to its first `len` bytes, so it can't be decoded.
+        fn truncate(mut self, len: usize) -> Self {
+            self.truncate = Some(len);
+            self
+        }
+
+        /// Makes the cursor animated, with this many frames after the first.
+        fn frames(mut self, frames: usize) -> Self {
+            self.frames = frames;
+            self
+        }
+
+        fn scroll(mut self, delta_x: f64, delta_y: f64) -> Self {
+            self.scroll = (delta_x, delta_y);
+            self
+        }
+
+        /// Blocks the recorder like a stuck native call, and ends the script early.
+        fn hang(mut self, duration: Duration) -> Self {
+            self.hang = Some(duration);
+            self
+        }
+
+        fn device(mut self, device_id: u32) -> Self {
+            self.device_id = device_id;
+            self
+        }
+
+        fn stale(mut self) -> Self {
+            self.stale = true;
+            self
+        }
+
+        /// Reports the press as seen by the input listener at `x, y`.
+        fn pressed_at(mut self, button: usize, x: i32, y: i32) -> Self {
+            self.buttons[button] = true;
+            self.press_position = Some((x, y));
+            self
+        }
+
+        /// Hands out `handle` as the cursor's identity, so the image is only fetched
+        /// when it changes.
+        fn handle(mut self, handle: u8) -> Self {
+            self.handle = Some(handle);
+            self
+        }
+
+        /// Panics when sampled, like a native call unwinding.
+        fn panic(mut self) -> Self {
+            self.panic = true;
+            self
+        }
+
+        /// Reports raw input, which keeps being available with no motion after this.
+        fn raw(mut self, delta_x: f64, delta_y: f64) -> Self {
+            self.raw_motion = Some((delta_x, delta_y));
+            self
+        }
+    }
+
+    /// Plays back one tick per recorder tick, then repeats the last one until stopped.
+    struct ScriptedSource {
+        ticks: VecDeque<Tick>,
+        current: Tick,
+        done: Option<oneshot::Sender<()>>,
+    }
+
+    impl CursorSource for ScriptedSource {
+        type Handle = u8;
+
+        fn mouse_state(&mut self) -> MouseState {
+            match self.ticks.pop_front() {
+                Some(tick) => self.current = tick,
+                None => {
+                    if let Some(done) = self.done.take() {
+                        done.send(()).ok();
+                    }
+                }
+            }
+
+            if self.current.panic {
+                panic!("scripted panic");
+            }
+
+            if let Some(hang) = self.current.hang.take() {
+                if let Some(done) = self.done.take() {
+                    done.send(()).ok();
+                }
+                std::thread::sleep(hang);
+            }
+
+            MouseState {
+                coords: self.current.coords,
+                button_pressed: self.current.buttons.to_vec(),
+            }
+        }
+
+        fn keys(&mut self) -> Vec<Keycode> {
+            self.current.keys.clone()
+        }
+
+        fn cursor_handle(&mut self) -> Option<Self::Handle> {
+            self.current.handle
+        }
+
+        #[cfg(not(feature = "cursor-images"))]
+        fn cursor_image(&mut self) -> Option<CursorData> {
+            None
+        }
+
+        #[cfg(feature = "cursor-images")]
+        fn cursor_image(&mut self) -> Option<CursorData> {
+            let shade = self.current.image?;
+            let mut image =
+                image::RgbaImage::from_pixel(8, 8, image::Rgba([shade, shade, shade, 255]));
+            if self.current.speck {
+                image.put_pixel(3, 3, image::Rgba([shade / 2, shade / 2, shade / 2, 255]));
+            }
+
+            let mut image = encode_png(&image)?;
+            if let Some(len) = self.current.truncate {
+                image.truncate(len);
+            }
+
+            Some(CursorData {
+                image,
+                hotspot: self.current.hotspot,
+                kind: self.current.kind,
+                system_name: None,
+                scale: 1.0,
+                frames: (1..=self.current.frames)
+                    .filter_map(|frame| {
+                        let shade = shade.wrapping_add(frame as u8);
+                        encode_png(&image::RgbaImage::from_pixel(
+                            8,
+                            8,
+                            image::Rgba([shade, shade, shade, 255]),
+                        ))
+                    })
+                    .collect(),
+                frame_duration_ms: 50.0,
+            })
+        }
+
+        fn cursor_visible(&mut self) -> bool {
+            self.current.visible
+        }
+
+        fn take_scroll_delta(&mut self) -> (f64, f64) {
+            std::mem::take(&mut self.current.scroll)
+        }
+
+        fn pointer_device(&mut self) -> u32 {
+            self.current.device_id
+        }
+
+        fn cursor_stale(&mut self) -> bool {
+            self.current.stale
+        }
+
+        fn take_press_position(&mut self, _button: u8) -> Option<(i32, i32)> {
+            self.current.press_position.take()
+        }
+
+        fn take_raw_motion(&mut self) -> Option<(f64, f64)> {
+            self.current.raw_motion.as_mut().map(std::mem::take)
+        }
+    }
+
+    /// Records `ticks`, the first of which is the starting state, and stops once they've
+    /// all been sampled.
+    async fn record(config: CursorRecorderConfig, ticks: Vec<Tick>) -> CursorActorResponse {
+        let (actor, done) = start(config, ticks);
+        done.await.unwrap();
+        actor.stop().await.unwrap()
+    }
+
+    fn start(
+        config: CursorRecorderConfig,
+        ticks: Vec<Tick>,
+    ) -> (CursorActor, oneshot::Receiver<()>) {
+        let (done_tx, done_rx) = oneshot::channel();
+        let source = ScriptedSource {
+            current: ticks[0].clone(),
+            ticks: ticks.into(),
+            done: Some(done_tx),
+        };
+
+        let actor = config
+            .poll_interval(Duration::from_millis(1))
+            .spawn_with_source(source)
+            .unwrap();
+        (actor, done_rx)
+    }
+
+    /// Counts the errors logged while it's the default subscriber.
+    #[cfg(feature = "cursor-images")]
+    struct ErrorCount(Arc<AtomicUsize>);
+
+    #[cfg(feature = "cursor-images")]
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for ErrorCount {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            if *event.metadata().level() == tracing::Level::ERROR {
+                self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("cap-cursor-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn modifiers_from_keys() {
+        assert_eq!(
+            active_modifiers(&[Keycode::A, Keycode::RShift, Keycode::LShift]),
+            vec!["Shift"]
+        );
+        assert_eq!(
+            active_modifiers(&[Keycode::Command, Keycode::LControl]),
+            vec!["Ctrl", "Meta"]
+        );
+        assert!(active_modifiers(&[Keycode::Space]).is_empty());
+    }
+
+    #[test]
+    fn unwritable_cursors_dir() {
+        let file = std::env::temp_dir().join(format!("cap-cursors-{}", std::process::id()));
+        std::fs::write(&file, []).unwrap();
+
+        // A directory can't be created underneath a regular file, even with elevated permissions
+        assert!(!ensure_cursors_dir(&file.join("cursors")));
+        assert!(ensure_cursors_dir(&std::env::temp_dir()));
+
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn preflight_checks_cursors_dir() {
+        let file = std::env::temp_dir().join(format!("cap-preflight-{}", std::process::id()));
+        std::fs::write(&file, []).unwrap();
+
+        assert!(matches!(
+            check_cursors_dir(&file.join("cursors")),
+            Err(CursorRecordError::CursorsDirUnwritable { .. })
+        ));
+        std::fs::remove_file(file).unwrap();
+
+        let dir = test_dir("preflight");
+        check_cursors_dir(&dir).unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(dir).unwrap();
+
+        // Invalid configs fail before anything is touched
+        let config =
+            CursorRecorderConfig::new(TEST_BOUNDS, PathBuf::new()).poll_interval(Duration::ZERO);
+        assert!(matches!(
+            config.preflight(),
+            Err(CursorRecordError::ZeroPollInterval)
+        ));
+    }
+
+    #[tokio::test]
+    async fn hands_events_to_the_sink() {
+        #[derive(Default)]
+        struct Seen {
+            moves: Vec<(f64, f64)>,
+            clicks: Vec<bool>,
+            summary: Option<CursorStats>,
+        }
+
+        struct TestSink(Arc<Mutex<Seen>>);
+
+        impl CursorEventSink for TestSink {
+            fn on_move(&mut self, event: CursorMoveEvent) {
+                self.0.lock().unwrap().moves.push((event.x, event.y));
+            }
+
+            fn on_click(&mut self, event: CursorClickEvent) {
+                self.0.lock().unwrap().clicks.push(event.down);
+            }
+
+            fn on_stop(&mut self, summary: &CursorStats) {
+                self.0.lock().unwrap().summary = Some(*summary);
+            }
+        }
+
+        let seen = Arc::new(Mutex::new(Seen::default()));
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("sink"))
+                .capture_images(false)
+                .sink(Box::new(TestSink(seen.clone()))),
+            vec![Tick::at(0, 0), Tick::at(10, 0).pressed(1), Tick::at(20, 0)],
+        )
+        .await;
+
+        assert!(response.moves.is_empty() && response.clicks.is_empty());
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.moves, vec![(0.1, 0.0), (0.2, 0.0)]);
+        assert_eq!(seen.clicks, vec![true, false]);
+        let summary = seen.summary.unwrap();
+        assert_eq!((summary.moves, summary.clicks), (2, 2));
+    }
+
+    #[test]
+    fn emit_drops_events_without_blocking() {
+        let scroll = CursorEvent::Scroll(CursorScrollEvent {
+            delta_x: 0.0,
+            delta_y: 1.0,
+            process_time_ms: 0.0,
+            unix_time_ms: 0.0,
+            x: 0.5,
+            y: 0.5,
+            monitor: Some(0),
+        });
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut events = EventSink::new(Some(tx), None, None);
+        events.emit(scroll.clone());
+        events.emit(scroll.clone());
+        assert!(matches!(rx.try_recv(), Ok(CursorEvent::Scroll(_))));
+        assert!(rx.try_recv().is_err());
+        assert_eq!(events.dropped, 1);
+
+        drop(rx);
+        events.emit(scroll);
+        assert!(events.sender.is_none());
+    }
+
+    #[test]
+    fn rejects_monitors_without_area() {
+        let zero_width = Bounds {
+            width: 0.0,
+            ..TEST_BOUNDS
+        };
+        let res = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("no-area"))
+            .monitors(vec![TEST_BOUNDS, zero_width])
+            .spawn();
+
+        assert!(matches!(
+            res,
+            Err(CursorRecordError::DegenerateMonitor { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn positions_past_the_edge() {
+        let bounds = Bounds {
+            x: 100.0,
+            y: 0.0,
+            width: 1000.0,
+            height: 500.0,
+        };
+        let position = |coords, drop| {
+            normalize_position(coords, &[bounds], drop).map(|p| (p.x, p.y, p.monitor))
+        };
+
+        // Dragging past the right edge onto the next monitor
+        for x in [1000, 1100, 1500, 3000] {
+            let (norm_x, _, _) = position((x, 250), false).unwrap();
+            assert!(norm_x <= 1.0);
+        }
+        assert_eq!(position((1500, 250), false), Some((1.0, 0.5, None)));
+        assert_eq!(position((50, -10), false), Some((0.0, 0.0, None)));
+
+        assert_eq!(position((1500, 250), true), None);
+        assert_eq!(position((600, 250), true), Some((0.5, 0.5, Some(0))));
+    }
+
+    #[tokio::test]
+    async fn flags_off_screen_moves() {
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("off-screen")).capture_images(false),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(50, 50),
+                Tick::at(150, 50),
+                Tick::at(60, 50),
+            ],
+        )
+        .await;
+
+        let moves: Vec<_> = response
+            .moves
+            .iter()
+            .map(|m| (m.x, m.monitor, m.off_screen))
+            .collect();
+        assert_eq!(
+            moves,
+            vec![
+                (0.5, Some(0), false),
+                (1.0, None, true),
+                (0.6, Some(0), false)
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn updates_bounds_mid_recording() {
+        let (actor, done) = start(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("update-bounds")).capture_images(false),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(50, 50),
+                // Holds the recorder while the bounds are updated
+                Tick::at(50, 50).hang(Duration::from_millis(100)),
+                Tick::at(100, 100),
+                Tick::at(150, 150),
+            ],
+        );
+        done.await.unwrap();
+
+        let doubled = Bounds {
+            width: 200.0,
+            height: 200.0,
+            ..TEST_BOUNDS
+        };
+        assert!(actor
+            .update_bounds(Bounds {
+                width: 0.0,
+                ..TEST_BOUNDS
+            })
+            .is_err());
+        actor.update_bounds(doubled).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let response = actor.stop().await.unwrap();
+
+        let moves: Vec<_> = response
+            .moves
+            .iter()
+            .map(|m| (m.screen_x, m.x, m.monitor))
+            .collect();
+        assert_eq!(
+            moves,
+            vec![
+                (50, 0.5, Some(0)),
+                (100, 0.5, Some(0)),
+                (150, 0.75, Some(0))
+            ]
+        );
+
+        // The change lands between the last move against the old bounds and the first
+        // against the new ones
+        assert_eq!(response.bounds_changes.len(), 1);
+        let change = response.bounds_changes[0];
+        assert_eq!(change.bounds.width, 200.0);
+        assert!(response.moves[0].process_time_ms <= change.process_time_ms);
+        assert!(change.process_time_ms <= response.moves[1].process_time_ms);
+        assert_eq!(response.end.unwrap().x, 0.75);
+    }
+
+    #[tokio::test]
+    async fn follows_window_bounds() {
+        let moved = Bounds {
+            x: 50.0,
+            ..TEST_BOUNDS
+        };
+        // Refreshing on every tick fetches the bounds once at the start and then once
+        // per tick, so each tick sees the next of these, with the last one repeating
+        let mut window = VecDeque::from([
+            TEST_BOUNDS,
+            TEST_BOUNDS,
+            // Minimizing the window keeps the last bounds
+            Bounds {
+                width: 0.0,
+                ..TEST_BOUNDS
+            },
+            moved,
+        ]);
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("window-bounds"))
+                .capture_images(false)
+                .window_bounds(
+                    move || {
+                        if window.len() > 1 {
+                            window.pop_front().unwrap()
+                        } else {
+                            window[0]
+                        }
+                    },
+                    Duration::ZERO,
+                ),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(50, 50),
+                Tick::at(50, 50),
+                Tick::at(75, 50),
+                Tick::at(20, 20),
+            ],
+        )
+        .await;
+
+        let moves: Vec<_> = response
+            .moves
+            .iter()
+            .map(|m| (m.x, m.monitor, m.off_screen))
+            .collect();
+        assert_eq!(
+            moves,
+            vec![
+                (0.5, Some(0), false),
+                (0.25, Some(0), false),
+                // Clamped to the window's edge
+                (0.0, None, true)
+            ]
+        );
+        assert_eq!(response.bounds_changes.len(), 1);
+        assert_eq!(response.bounds_changes[0].bounds.x, 50.0);
+    }
+
+    #[tokio::test]
+    async fn tags_events_with_monitor_ids() {
+        let second = Bounds {
+            x: 100.0,
+            ..TEST_BOUNDS
+        };
+        let config = || {
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("monitor-ids"))
+                .monitors(vec![TEST_BOUNDS, second])
+                .capture_images(false)
+        };
+        assert!(matches!(
+            config().monitor_ids(vec![7]).spawn(),
+            Err(CursorRecordError::MonitorIdsMismatch {
+                ids: 1,
+                monitors: 2
+            })
+        ));
+
+        let response = record(
+            config().monitor_ids(vec![7, 3]),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(50, 50),
+                Tick::at(150, 50).pressed(1),
+                Tick::at(250, 50),
+            ],
+        )
+        .await;
+
+        let moves: Vec<_> = response.moves.iter().map(|m| m.monitor_id).collect();
+        assert_eq!(moves, vec![Some(7), Some(3), None]);
+        assert_eq!(response.clicks[0].monitor_id, Some(3));
+
+        // Without ids, the index stands in
+        let response = record(config(), vec![Tick::at(0, 0), Tick::at(150, 50)]).await;
+        assert_eq!(response.moves[0].monitor_id, Some(1));
+    }
+
+    #[test]
+    fn positions_across_monitors() {
+        let monitors = [
+            Bounds {
+                x: 0.0,
+                y: 0.0,
+                width: 1920.0,
+                height: 1080.0,
+            },
+            Bounds {
+                x: 1920.0,
+                y: 0.0,
+                width: 1280.0,
+                height: 720.0,
+            },
+        ];
+        let position =
+            |coords| normalize_position(coords, &monitors, true).map(|p| (p.x, p.y, p.monitor));
+
+        assert_eq!(position((960, 540)), Some((0.5, 0.5, Some(0))));
+        assert_eq!(position((2560, 360)), Some((0.5, 0.5, Some(1))));
+        // Below the shorter second monitor
+        assert_eq!(position((2560, 900)), None);
+    }
+
+    #[test]
+    fn coalesces_small_moves() {
+        let mv = |time: f64| CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "default".to_string(),
+            process_time_ms: time,
+            unix_time_ms: time,
+            x: 0.0,
+            y: 0.0,
+            screen_x: 0,
+            screen_y: 0,
+            monitor: Some(0),
+            monitor_id: Some(0),
+            device_id: 0,
+            visible: true,
+            off_screen: false,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            acceleration_x: 0.0,
+            acceleration_y: 0.0,
+            delta: None,
+        };
+        let time = |event: Option<CursorMoveEvent>| event.map(|e| e.process_time_ms);
+
+        // The default threshold keeps every pixel
+        let mut lossless = MoveCoalescer::new(DEFAULT_MOVE_THRESHOLD, DEFAULT_COALESCE_WINDOW);
+        assert_eq!(time(lossless.push((0, 0), mv(0.0))), Some(0.0));
+        assert_eq!(time(lossless.push((1, 0), mv(10.0))), Some(10.0));
+
+        let mut coalescer = MoveCoalescer::new(5.0, Duration::from_millis(100));
+        // The first point of a gesture is always kept
+        assert_eq!(time(coalescer.push((0, 0), mv(0.0))), Some(0.0));
+        assert_eq!(time(coalescer.push((1, 0), mv(10.0))), None);
+        assert_eq!(time(coalescer.push((2, 0), mv(20.0))), None);
+        assert_eq!(time(coalescer.push((10, 0), mv(30.0))), Some(30.0));
+        assert_eq!(time(coalescer.push((11, 0), mv(40.0))), None);
+        // ...and so is the last, once the cursor comes to rest
+        assert_eq!(time(coalescer.flush()), Some(40.0));
+        assert_eq!(time(coalescer.flush()), None);
+
+        // Slow drags are still sampled once per window
+        assert_eq!(time(coalescer.push((12, 0), mv(100.0))), None);
+        assert_eq!(time(coalescer.push((13, 0), mv(150.0))), Some(150.0));
+    }
+
+    #[test]
+    fn drags_need_movement_between_press_and_release() {
+        let mut drags = DragTracker::default();
+
+        // A plain click isn't a drag
+        drags.press(1, XY::new(0.1, 0.1), 0.0);
+        assert!(drags.release(1, XY::new(0.1, 0.1), 50.0).is_none());
+
+        drags.press(1, XY::new(0.1, 0.1), 100.0);
+        drags.moved();
+        // Releasing a different button doesn't end the drag
+        assert!(drags.release(2, XY::new(0.5, 0.5), 150.0).is_none());
+
+        let drag = drags.release(1, XY::new(0.5, 0.6), 200.0).unwrap();
+        assert_eq!(drag.cursor_num, 1);
+        assert_eq!(drag.start, XY::new(0.1, 0.1));
+        assert_eq!(drag.end, XY::new(0.5, 0.6));
+        assert_eq!(drag.start_process_time_ms, 100.0);
+        assert_eq!(drag.end_process_time_ms, 200.0);
+
+        // Movement with nothing held doesn't start a drag
+        drags.moved();
+        drags.press(1, XY::new(0.5, 0.6), 300.0);
+        assert!(drags.release(1, XY::new(0.5, 0.6), 350.0).is_none());
+    }
+
+    #[test]
+    fn counts_double_clicks() {
+        let mut clicks = ClickCounter::new(DEFAULT_DOUBLE_CLICK_INTERVAL, 4.0);
+
+        assert_eq!(clicks.press(1, (100, 100), 0.0), 1);
+        assert_eq!(clicks.release(1, (100, 100), 80.0), 1);
+        assert_eq!(clicks.press(1, (102, 101), 200.0), 2);
+        assert_eq!(clicks.release(1, (102, 101), 280.0), 2);
+        assert_eq!(clicks.press(1, (102, 101), 400.0), 3);
+        assert_eq!(clicks.release(1, (102, 101), 480.0), 3);
+
+        // Too slow
+        assert_eq!(clicks.press(1, (102, 101), 1000.0), 1);
+        assert_eq!(clicks.release(1, (102, 101), 1080.0), 1);
+        // Too far
+        assert_eq!(clicks.press(1, (200, 101), 1200.0), 1);
+        assert_eq!(clicks.release(1, (200, 101), 1280.0), 1);
+        // Different button
+        assert_eq!(clicks.press(2, (200, 101), 1300.0), 1);
+    }
+
+    #[test]
+    fn spots_stuck_input() {
+        let after_ms = STUCK_INPUT_AFTER.as_secs_f64() * 1000.0;
+        let mut stuck = StuckInput::default();
+
+        assert!(!stuck.sample((0, 0), 0, 0.0));
+        // Sitting still without clicking is just idle
+        assert!(!stuck.sample((0, 0), 0, after_ms * 2.0));
+        for i in 1..STUCK_INPUT_ACTIONS {
+            assert!(!stuck.sample((0, 0), 1, after_ms * 2.0 + i as f64));
+        }
+        assert!(stuck.sample((0, 0), 1, after_ms * 3.0));
+        assert!(stuck.sample((0, 0), 0, after_ms * 3.0 + 10.0));
+
+        // Any movement starts over
+        assert!(!stuck.sample((1, 0), STUCK_INPUT_ACTIONS, after_ms * 3.0 + 20.0));
+        assert!(!stuck.sample((1, 0), 0, after_ms * 3.0 + 30.0));
+        assert!(stuck.sample((1, 0), STUCK_INPUT_ACTIONS, after_ms * 4.0 + 20.0));
+    }
+
+    #[test]
+    fn visibility_changes_are_kept() {
+        let mv = |time: f64, visible: bool| CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "default".to_string(),
+            process_time_ms: time,
+            unix_time_ms: time,
+            x: 0.5,
+            y: 0.5,
+            screen_x: 50,
+            screen_y: 50,
+            monitor: Some(0),
+            monitor_id: Some(0),
+            device_id: 0,
+            visible,
+            off_screen: false,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            acceleration_x: 0.0,
+            acceleration_y: 0.0,
+            delta: None,
+        };
+        let visible = |event: Option<CursorMoveEvent>| event.map(|e| e.visible);
+
+        // Even with aggressive coalescing, a cursor hidden in place must be recorded
+        let mut coalescer = MoveCoalescer::new(50.0, Duration::from_secs(10));
+        assert_eq!(visible(coalescer.push((10, 10), mv(0.0, true))), Some(true));
+        assert_eq!(
+            visible(coalescer.push((10, 10), mv(10.0, false))),
+            Some(false)
+        );
+        assert_eq!(visible(coalescer.push((12, 10), mv(20.0, false))), None);
+        assert_eq!(
+            visible(coalescer.push((12, 10), mv(30.0, true))),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn screen_size_from_scaled_image() {
+        // A 64px image of a 32pt cursor, on a 1600x1000 point display
+        let bounds = Bounds {
+            x: 0.0,
+            y: 0.0,
+            width: 1600.0,
+            height: 1000.0,
+        };
+        assert_eq!(
+            screen_size(XY::new(64, 64), 2.0, 1.0, &bounds),
+            XY::new(0.02, 0.032)
+        );
+        // Where positions are physical pixels, the image covers as many of them
+        assert_eq!(
+            screen_size(XY::new(64, 64), 2.0, 2.0, &bounds),
+            XY::new(0.04, 0.064)
+        );
+    }
+
+    #[test]
+    fn image_hash_is_stable() {
+        // Pinned, as it also keys cursors persisted by earlier builds
+        assert_eq!(cursor_image_hash(b"abc"), 0xba7816bf8f01cfea);
+        assert_ne!(cursor_image_hash(b"abc"), cursor_image_hash(b"abd"));
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[test]
+    fn normalized_size_keeps_hotspot() {
+        // A red mark in a wide image, right at the hotspot
+        let mut image = image::RgbaImage::from_pixel(16, 8, image::Rgba([0, 0, 0, 255]));
+        for (x, y) in [(4, 2), (5, 2), (4, 3), (5, 3)] {
+            image.put_pixel(x, y, image::Rgba([255, 0, 0, 255]));
+        }
+        let mut data = CursorData {
+            image: encode_png(&image).unwrap(),
+            hotspot: XY::new(4.0 / 16.0, 2.0 / 8.0),
+            kind: CursorKind::Custom,
+            system_name: None,
+            scale: 1.0,
+            frames: vec![encode_png(&image).unwrap()],
+            frame_duration_ms: 50.0,
+        };
+
+        normalize_cursor_size(&mut data, (64, 64));
+
+        // Scaled 4x to 64x32, then centered vertically
+        assert_eq!(image_dimensions(&data.image), Some((64, 64)));
+        assert_eq!(image_dimensions(&data.frames[0]), Some((64, 64)));
+        assert_eq!(data.hotspot, XY::new(0.25, 0.375));
+        assert_eq!(data.scale, 4.0);
+
+        let resized = image::load_from_memory(&data.image).unwrap().to_rgba8();
+        let hotspot = hotspot_pixels(data.hotspot, XY::new(64, 64));
+        let mark = resized.get_pixel(hotspot.x + 4, hotspot.y + 4);
+        assert!(mark[0] > 200 && mark[1] < 50, "{mark:?}");
+        // The padding is transparent
+        assert_eq!(resized.get_pixel(0, 0)[3], 0);
+    }
+
+    #[test]
+    fn hotspot_pixels_round_trip() {
+        // Like a 32px X11 cursor with its hotspot at (5, 27)
+        let hotspot = XY::new(5.0 / 32.0, 27.0 / 32.0);
+        assert_eq!(hotspot_pixels(hotspot, XY::new(32, 32)), XY::new(5, 27));
+        assert_eq!(
+            hotspot_pixels(XY::new(0.0, 0.0), XY::new(0, 0)),
+            XY::new(0, 0)
+        );
+    }
+
+    #[test]
+    fn project_cursors_round_trip() {
+        let response = CursorActorResponse {
+            cursor_images: HashMap::new(),
+            cursors: Cursors::from([(
+                42,
+                Cursor {
+                    file_name: Some("cursor_0.png".to_string()),
+                    id: 0,
+                    hotspot: XY::new(0.25, 0.5),
+                    hotspot_px: XY::new(16, 32),
+                    kind: CursorKind::Arrow,
+                    system_name: None,
+                    scale: 2.0,
+                    resolution: XY::new(64, 64),
+                    screen_size: XY::new(0.02, 0.03),
+                    animation: None,
+                    possibly_stale: false,
+                },
+            )]),
+            next_cursor_id: 1,
+            cursor_files_written: 1,
+            moves: vec![CursorMoveEvent {
+                active_modifiers: vec!["Shift".to_string()],
+                cursor_id: "0".to_string(),
+                process_time_ms: 10.0,
+                unix_time_ms: 1000.0,
+                x: 0.5,
+                y: 0.75,
+                screen_x: 50,
+                screen_y: 75,
+                monitor: Some(0),
+                monitor_id: Some(0),
+                device_id: 0,
+                visible: true,
+                off_screen: false,
+                velocity_x: 0.0,
+                velocity_y: 0.0,
+                acceleration_x: 0.0,
+                acceleration_y: 0.0,
+                delta: None,
+            }],
+            clicks: vec![CursorClickEvent {
+                active_modifiers: vec![],
+                cursor_num: 1,
+                button: Some(MouseButton::Left),
+                cursor_id: "0".to_string(),
+                process_time_ms: 20.0,
+                unix_time_ms: 1010.0,
+                down: true,
+                duration_ms: None,
+                click_count: 1,
+                x: 0.5,
+                y: 0.75,
+                screen_x: 50,
+                screen_y: 75,
+                monitor: Some(0),
+                monitor_id: Some(0),
+                device_id: 0,
+                frame: None,
+            }],
+            scrolls: vec![],
+            drags: vec![],
+            click_counts: HashMap::new(),
+            modifier_events: vec![],
+            busy_events: vec![],
+            image_capture_disabled: false,
+            cursor_limit_reached: false,
+            input_possibly_stuck: false,
+            auto_stopped: false,
+            duration: Duration::from_millis(20),
+            sample_count: 2,
+            overruns: 0,
+            poll_interval_changes: vec![],
+            bounds_changes: vec![],
+            dropped_stream_events: 0,
+            start: None,
+            end: None,
+        };
+
+        let meta = project_cursor_meta(&response.cursors, RelativePath::new("content/cursors"));
+        let project = response.into_project_cursors();
+        assert_eq!(project.next_cursor_id, 1);
+
+        let events: CursorEvents =
+            serde_json::from_str(&serde_json::to_string(&project.events).unwrap()).unwrap();
+        assert_eq!(events.moves.len(), 1);
+        assert_eq!(events.moves[0].cursor_id, "0");
+        assert_eq!((events.moves[0].x, events.moves[0].y), (0.5, 0.75));
+        assert_eq!(events.moves[0].active_modifiers, vec!["Shift"]);
+        assert_eq!(events.clicks.len(), 1);
+        assert!(events.clicks[0].down);
+        assert_eq!(events.clicks[0].process_time_ms, 20.0);
+
+        let meta: cap_project::Cursors =
+            serde_json::from_str(&serde_json::to_string(&meta).unwrap()).unwrap();
+        let cap_project::Cursors::Correct(meta) = meta else {
+            panic!("cursor meta parsed as the legacy format");
+        };
+        assert_eq!(meta["0"].image_path, "content/cursors/cursor_0.png");
+        assert_eq!(meta["0"].hotspot, XY::new(0.25, 0.5));
+        assert_eq!(meta["0"].scale, 2.0);
+    }
+
+    #[tokio::test]
+    async fn records_scripted_gestures() {
+        let config =
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("gestures")).capture_images(false);
+        let response = record(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 10),
+                Tick::at(10, 10).pressed(1).keys(&[Keycode::LShift]),
+                Tick::at(50, 20).pressed(1),
+                Tick::at(50, 20),
+                Tick::at(50, 20).scroll(0.0, -1.0),
+            ],
+        )
+        .await;
+
+        let moves: Vec<_> = response.moves.iter().map(|m| (m.x, m.y)).collect();
+        assert_eq!(moves, vec![(0.1, 0.1), (0.5, 0.2)]);
+        let screen_moves: Vec<_> = response
+            .moves
+            .iter()
+            .map(|m| (m.screen_x, m.screen_y))
+            .collect();
+        assert_eq!(screen_moves, vec![(10, 10), (50, 20)]);
+        assert!(response.moves.iter().all(|m| m.cursor_id == "default"));
+
+        assert_eq!(response.clicks.len(), 2);
+        assert!(response.clicks[0].down);
+        assert_eq!(response.clicks[0].cursor_num, 1);
+        assert_eq!(response.clicks[0].button, Some(MouseButton::Left));
+        assert_eq!(
+            (response.clicks[0].screen_x, response.clicks[0].screen_y),
+            (10, 10)
+        );
+        assert_eq!(response.clicks[0].active_modifiers, vec!["Shift"]);
+        assert!(!response.clicks[1].down);
+        assert!(response.clicks[1].active_modifiers.is_empty());
+
+        assert_eq!(response.drags.len(), 1);
+        assert_eq!(response.drags[0].start, XY::new(0.1, 0.1));
+        assert_eq!(response.drags[0].end, XY::new(0.5, 0.2));
+
+        assert_eq!(response.scrolls.len(), 1);
+        assert_eq!(response.scrolls[0].delta_y, -1.0);
+    }
+
+    #[tokio::test]
+    async fn counts_presses_per_button() {
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("click-counts"))
+                .capture_images(false)
+                .filter(|event| !matches!(event, CursorEvent::Click(_))),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(0, 0).pressed(1),
+                Tick::at(0, 0),
+                Tick::at(0, 0).pressed(1).pressed(2),
+                Tick::at(0, 0),
+            ],
+        )
+        .await;
+
+        assert!(response.clicks.is_empty());
+        assert_eq!(response.click_counts.len(), 2);
+        assert_eq!(response.click_counts[&MouseButton::Left], 2);
+        assert_eq!(response.click_counts[&mouse_button(2)], 1);
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn records_busy_state_changes() {
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("busy-state")),
+            vec![
+                Tick::at(0, 0).image(10),
+                Tick::at(10, 0).image(20).kind(CursorKind::Wait),
+                // Neither a failed capture nor moving between busy cursors ends it
+                Tick::at(15, 0),
+                Tick::at(20, 0).image(30).kind(CursorKind::Wait),
+                Tick::at(30, 0).image(10),
+                Tick::at(40, 0).image(20).kind(CursorKind::Wait),
+            ],
+        )
+        .await;
+
+        let changes: Vec<_> = response.busy_events.iter().map(|e| e.busy).collect();
+        assert_eq!(changes, vec![true, false, true]);
+        assert!(response
+            .busy_events
+            .windows(2)
+            .all(|pair| pair[0].process_time_ms <= pair[1].process_time_ms));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn keeps_only_the_replay_window() {
+        let ticks = vec![
+            Tick::at(0, 0),
+            Tick::at(10, 10).pressed(1),
+            Tick::at(20, 20),
+            Tick::at(30, 30),
+        ];
+
+        let (actor, done) = start(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("replay-window-long"))
+                .capture_images(false)
+                .coalesce_moves(0.0, Duration::ZERO)
+                .replay_window(Duration::from_secs(3600)),
+            ticks.clone(),
+        );
+        done.await.unwrap();
+        let snapshot = actor.snapshot();
+        assert_eq!(snapshot.moves.len(), 3);
+        assert_eq!(snapshot.clicks.len(), 2);
+        assert_eq!(snapshot.drags.len(), 1);
+        assert!(snapshot.end.is_none());
+
+        // Snapshots leave the buffered events in place
+        let response = actor.stop().await.unwrap();
+        let times = |moves: &[CursorMoveEvent]| -> Vec<f64> {
+            moves.iter().map(|m| m.process_time_ms).collect()
+        };
+        assert_eq!(times(&response.moves), times(&snapshot.moves));
+        assert_eq!(response.clicks.len(), snapshot.clicks.len());
+
+        // Everything from the script is older than an empty window by the time the
+        // recorder has gone on ticking for a while
+        let (actor, done) = start(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("replay-window-empty"))
+                .capture_images(false)
+                .coalesce_moves(0.0, Duration::ZERO)
+                .replay_window(Duration::ZERO),
+            ticks,
+        );
+        done.await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let snapshot = actor.snapshot();
+        assert!(snapshot.moves.is_empty());
+        assert!(snapshot.clicks.is_empty());
+        assert!(snapshot.drags.is_empty());
+
+        let response = actor.stop().await.unwrap();
+        assert!(response.moves.is_empty());
+        assert_eq!(response.click_counts[&MouseButton::Left], 1);
+    }
+
+    #[tokio::test]
+    async fn records_only_the_chosen_buttons() {
+        let ticks = vec![
+            Tick::at(0, 0),
+            Tick::at(0, 0).pressed(1),
+            Tick::at(10, 0).pressed(3),
+            Tick::at(20, 0),
+        ];
+
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("recorded-buttons"))
+                .capture_images(false)
+                .recorded_buttons([MouseButton::Left]),
+            ticks.clone(),
+        )
+        .await;
+        let clicks: Vec<_> = response.clicks.iter().map(|c| (c.button, c.down)).collect();
+        assert_eq!(
+            clicks,
+            vec![
+                (Some(MouseButton::Left), true),
+                (Some(MouseButton::Left), false)
+            ]
+        );
+        assert_eq!(response.drags.len(), 1);
+        assert_eq!(response.click_counts.len(), 1);
+
+        // No buttons at all
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("no-recorded-buttons"))
+                .capture_images(false)
+                .recorded_buttons([]),
+            ticks,
+        )
+        .await;
+        assert!(response.clicks.is_empty());
+        assert!(response.drags.is_empty());
+        assert!(response.click_counts.is_empty());
+        assert_eq!(response.moves.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn times_how_long_clicks_were_held() {
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("held-clicks")).capture_images(false),
+            vec![
+                Tick::at(0, 0).pressed(1),
+                Tick::at(0, 0),
+                Tick::at(0, 0).pressed(1),
+                Tick::at(0, 0).pressed(1),
+                Tick::at(0, 0),
+            ],
+        )
+        .await;
+
+        let clicks: Vec<_> = response
+            .clicks
+            .iter()
+            .map(|c| (c.down, c.duration_ms.is_some()))
+            .collect();
+        // The button held at the start has no press to pair its release with
+        assert_eq!(clicks, vec![(false, false), (true, false), (false, true)]);
+
+        let held = response.clicks[2].duration_ms.unwrap();
+        assert!(held > 0.0);
+        assert!(
+            (held - (response.clicks[2].process_time_ms - response.clicks[1].process_time_ms))
+                .abs()
+                < 1e-9
+        );
+    }
+
+    #[tokio::test]
+    async fn places_presses_where_the_listener_saw_them() {
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("press-positions"))
+                .capture_images(false),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(20, 0),
+                // Pressed at 30 on the way from 20 to 40
+                Tick::at(40, 0).pressed_at(1, 30, 0),
+                Tick::at(60, 0),
+                Tick::at(80, 0).pressed(1),
+            ],
+        )
+        .await;
+
+        let clicks: Vec<_> = response
+            .clicks
+            .iter()
+            .map(|c| (c.down, c.screen_x, c.x))
+            .collect();
+        // Releases, and presses the listener didn't see, use the polled position
+        assert_eq!(
+            clicks,
+            vec![(true, 30, 0.3), (false, 60, 0.6), (true, 80, 0.8)]
+        );
+        assert_eq!(response.drags[0].start.x, 0.3);
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn saves_each_distinct_cursor_once() {
+        let dir = test_dir("distinct");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![
+                Tick::at(0, 0).image(10),
+                Tick::at(1, 0).image(10),
+                Tick::at(2, 0).image(200),
+                Tick::at(3, 0).image(10),
+            ],
+        )
+        .await;
+
+        assert_eq!(response.cursors.len(), 2);
+        assert_eq!(response.next_cursor_id, 2);
+        for cursor in response.cursors.values() {
+            assert_eq!(cursor.resolution, XY::new(8, 8));
+            assert_eq!(cursor.hotspot_px, XY::new(0, 0));
+            assert!(dir.join(cursor.file_name.as_ref().unwrap()).exists());
+        }
+
+        let ids: Vec<_> = response
+            .moves
+            .iter()
+            .map(|m| m.cursor_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["0", "1", "0"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn records_only_clicks_without_moves() {
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("no-moves"))
+                .keep_images_in_memory(true)
+                .record_moves(false),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0).image(10),
+                Tick::at(20, 0).image(20).pressed(1),
+                Tick::at(30, 0).image(10).pressed(1),
+                Tick::at(40, 0).image(30),
+            ],
+        )
+        .await;
+
+        assert!(response.moves.is_empty());
+        let clicks: Vec<_> = response
+            .clicks
+            .iter()
+            .map(|c| (c.down, c.x, c.cursor_id.as_str()))
+            .collect();
+        assert_eq!(clicks, vec![(true, 0.2, "0"), (false, 0.4, "1")]);
+        // Cursors on ticks without a click are never captured
+        assert_eq!(response.cursors.len(), 2);
+        assert_eq!(response.drags.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn records_motion_deltas() {
+        let deltas = |response: &CursorActorResponse| {
+            response
+                .moves
+                .iter()
+                .map(|m| m.delta.map(|d| (d.x, d.y, d.raw)))
+                .collect::<Vec<_>>()
+        };
+
+        // Raw input moves the cursor even where its position doesn't change
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("raw-deltas"))
+                .coalesce_moves(0.0, Duration::ZERO)
+                .record_motion_deltas(true),
+            vec![
+                Tick::at(50, 50).raw(0.0, 0.0),
+                Tick::at(50, 50).raw(5.0, -2.0),
+                Tick::at(50, 50).raw(0.0, 0.0),
+                Tick::at(60, 50).raw(3.0, 0.0),
+            ],
+        )
+        .await;
+        assert_eq!(
+            deltas(&response),
+            vec![Some((5.0, -2.0, true)), Some((3.0, 0.0, true))]
+        );
+
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("computed-deltas"))
+                .coalesce_moves(0.0, Duration::ZERO)
+                .record_motion_deltas(true),
+            vec![Tick::at(50, 50), Tick::at(60, 45), Tick::at(60, 45)],
+        )
+        .await;
+        assert_eq!(deltas(&response), vec![Some((10.0, -5.0, false))]);
+
+        // Moves held back by coalescing pass their motion on
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("coalesced-deltas"))
+                .coalesce_moves(100.0, Duration::from_secs(60))
+                .record_motion_deltas(true),
+            vec![
+                Tick::at(0, 0).raw(0.0, 0.0),
+                Tick::at(1, 0).raw(1.0, 0.0),
+                Tick::at(2, 0).raw(1.0, 0.0),
+                Tick::at(3, 0).raw(1.0, 0.0),
+                Tick::at(3, 0).raw(0.0, 0.0),
+            ],
+        )
+        .await;
+        assert_eq!(
+            deltas(&response),
+            vec![Some((1.0, 0.0, true)), Some((2.0, 0.0, true))]
+        );
+
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("no-deltas")),
+            vec![
+                Tick::at(50, 50).raw(0.0, 0.0),
+                Tick::at(60, 50).raw(3.0, 0.0),
+            ],
+        )
+        .await;
+        assert_eq!(deltas(&response), vec![None]);
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn hashes_images_with_the_given_hasher() {
+        let ticks = vec![
+            Tick::at(0, 0),
+            Tick::at(10, 0).image(10),
+            Tick::at(20, 0).image(20),
+            Tick::at(30, 0).image(10),
+        ];
+
+        // Every image collides, so they're all the first cursor
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("image-hasher"))
+                .keep_images_in_memory(true)
+                .image_hasher(|_| 7),
+            ticks.clone(),
+        )
+        .await;
+        assert_eq!(response.cursors.keys().collect::<Vec<_>>(), vec![&7]);
+        assert!(response.moves.iter().all(|m| m.cursor_id == "0"));
+
+        let ids = |response: &CursorActorResponse| {
+            let mut cursors: Vec<_> = response
+                .cursors
+                .iter()
+                .map(|(hash, cursor)| (*hash, cursor.id))
+                .collect();
+            cursors.sort();
+            cursors
+        };
+        fn hasher(image: &[u8]) -> u64 {
+            image
+                .iter()
+                .fold(0, |hash: u64, &byte| hash.wrapping_mul(31) + byte as u64)
+        }
+        let first = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("image-hasher-first"))
+                .keep_images_in_memory(true)
+                .image_hasher(hasher),
+            ticks.clone(),
+        )
+        .await;
+        let second = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("image-hasher-second"))
+                .keep_images_in_memory(true)
+                .image_hasher(hasher),
+            ticks,
+        )
+        .await;
+        assert_eq!(ids(&first).len(), 2);
+        assert_eq!(ids(&first), ids(&second));
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn retains_saved_images() {
+        let dir = test_dir("retain-images");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()).retain_saved_images(true),
+            vec![Tick::at(0, 0).image(10), Tick::at(10, 0).image(10)],
+        )
+        .await;
+
+        assert_eq!(response.cursor_files_written, 1);
+        let image = image::load_from_memory(response.image_bytes(0).unwrap()).unwrap();
+        let saved = image::open(dir.join("cursor_0.png")).unwrap();
+        assert_eq!(image.into_rgba8(), saved.into_rgba8());
+        assert!(response.image_bytes(1).is_none());
+
+        // Nothing is kept by default
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("no-retained-images")),
+            vec![Tick::at(0, 0).image(10), Tick::at(10, 0).image(10)],
+        )
+        .await;
+        assert_eq!(response.cursor_files_written, 1);
+        assert!(response.image_bytes(0).is_none());
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn grabs_the_cursor_again_on_click() {
+        // The app swaps the image on press without handing out a new cursor
+        let ticks = vec![
+            Tick::at(0, 0).handle(1).image(10),
+            Tick::at(10, 0).handle(1).image(10),
+            Tick::at(10, 0).handle(1).image(20).pressed(1),
+            Tick::at(20, 0).handle(1).image(20).pressed(1),
+        ];
+        let click_cursors = |response: &CursorActorResponse| {
+            response
+                .clicks
+                .iter()
+                .map(|c| c.cursor_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("cached-click-cursor"))
+                .keep_images_in_memory(true),
+            ticks.clone(),
+        )
+        .await;
+        assert_eq!(click_cursors(&response), vec!["0"]);
+
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("fresh-click-cursor"))
+                .keep_images_in_memory(true)
+                .grab_cursor_on_click(true),
+            ticks,
+        )
+        .await;
+        assert_eq!(click_cursors(&response), vec!["1"]);
+        // Moves after the click keep using the cache, which now holds the new cursor
+        assert_eq!(response.moves.last().unwrap().cursor_id, "1");
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn stops_saving_cursors_past_the_limit() {
+        let dir = test_dir("max-cursors");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone())
+                .keep_images_in_memory(true)
+                .max_cursors(2),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(1, 0).image(10),
+                Tick::at(2, 0).image(20),
+                Tick::at(3, 0).image(30),
+                Tick::at(4, 0).image(10),
+                Tick::at(5, 0).image(40),
+            ],
+        )
+        .await;
+
+        assert!(response.cursor_limit_reached);
+        assert_eq!(response.cursors.len(), 2);
+        let ids: Vec<_> = response
+            .moves
+            .iter()
+            .map(|m| m.cursor_id.as_str())
+            .collect();
+        // Cursors seen before the limit are still recognized
+        assert_eq!(ids, vec!["0", "1", "default", "0", "default"]);
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn flags_possibly_stale_cursors() {
+        let dir = test_dir("stale");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(1, 0).image(10),
+                Tick::at(2, 0).image(10).stale(),
+                Tick::at(3, 0).image(200),
+            ],
+        )
+        .await;
+
+        let stale: HashMap<_, _> = response
+            .cursors
+            .values()
+            .map(|cursor| (cursor.id, cursor.possibly_stale))
+            .collect();
+        assert_eq!(stale, HashMap::from([(0, true), (1, false)]));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn unreadable_images_use_the_default_cursor() {
+        let dir = test_dir("unreadable");
+        // The first 60 bytes keep the PNG header, so this one only fails once decoded
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(1, 0).image(10),
+                Tick::at(2, 0).image(20).truncate(0),
+                Tick::at(3, 0).image(30).truncate(60),
+                Tick::at(4, 0).image(30).truncate(60),
+                Tick::at(5, 0).image(10),
+            ],
+        )
+        .await;
+
+        assert_eq!(response.cursors.len(), 1);
+        // Only the truncated image got as far as being saved
+        assert_eq!(response.next_cursor_id, 2);
+        for cursor in response.cursors.values() {
+            assert!(dir.join(cursor.file_name.as_ref().unwrap()).exists());
+        }
+
+        let ids: Vec<_> = response
+            .moves
+            .iter()
+            .map(|m| m.cursor_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["0", "default", "default", "default", "0"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn smooth_reduces_jitter() {
+        let mv = |time: f64, x: f64, y: f64| CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "default".to_string(),
+            process_time_ms: time,
+            unix_time_ms: time,
+            x,
+            y,
+            screen_x: 0,
+            screen_y: 0,
+            monitor: Some(0),
+            monitor_id: Some(0),
+            device_id: 0,
+            visible: true,
+            off_screen: false,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            acceleration_x: 0.0,
+            acceleration_y: 0.0,
+            delta: None,
+        };
+
+        // A straight diagonal, with every other sample a pixel-ish off it
+        let noise = |i: usize| [0.004, -0.004][i % 2];
+        let mut response = CursorActorResponse {
+            moves: (0..50)
+                .map(|i| {
+                    let t = i as f64 / 100.0;
+                    mv(i as f64 * 10.0, t + noise(i), t - noise(i))
+                })
+                .collect(),
+            clicks: vec![CursorClickEvent {
+                active_modifiers: vec![],
+                cursor_num: 1,
+                button: Some(MouseButton::Left),
+                cursor_id: "default".to_string(),
+                process_time_ms: 205.0,
+                unix_time_ms: 205.0,
+                down: true,
+                duration_ms: None,
+                click_count: 1,
+                x: 0.2,
+                y: 0.2,
+                screen_x: 0,
+                screen_y: 0,
+                monitor: Some(0),
+                monitor_id: Some(0),
+                device_id: 0,
+                frame: None,
+            }],
+            ..Default::default()
+        };
+        let original = response.moves.clone();
+
+        let error = |moves: &[CursorMoveEvent]| -> f64 {
+            moves
+                .iter()
+                .enumerate()
+                .map(|(i, m)| {
+                    let t = i as f64 / 100.0;
+                    (m.x - t).powi(2) + (m.y - t).powi(2)
+                })
+                .sum()
+        };
+
+        response.smooth(5);
+
+        assert!(error(&response.moves) < error(&original) / 4.0);
+        for i in [0, 20, 21, 49] {
+            assert_eq!(
+                (response.moves[i].x, response.moves[i].y),
+                (original[i].x, original[i].y),
+                "move {i} should be anchored"
+            );
+        }
+        assert_eq!(response.clicks[0].x, 0.2);
+    }
+
+    #[tokio::test]
+    async fn draws_path_as_svg() {
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("svg")).capture_images(false),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(50, 0),
+                Tick::at(50, 50).pressed(1),
+                Tick::at(100, 100),
+            ],
+        )
+        .await;
+
+        let svg = response.to_svg(200, 100);
+        assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<polyline").count(), 1);
+        assert!(svg.contains(r#"points="100.0,0.0 100.0,50.0 200.0,100.0""#));
+        // Only the press is marked, not the release
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert!(svg.contains(r#"<circle cx="100.0" cy="50.0""#));
+    }
+
+    #[tokio::test]
+    async fn reports_panics_on_stop() {
+        let (actor, done) = start(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("panic")).capture_images(false),
+            vec![Tick::at(0, 0), Tick::at(10, 0), Tick::at(20, 0).panic()],
+        );
+        // The source is dropped along with the recorder, without finishing its script
+        assert!(done.await.is_err());
+
+        match actor.stop().await {
+            Err(CursorRecordError::Panicked(message)) => assert_eq!(message, "scripted panic"),
+            res => panic!("expected a panic, got {:?}", res.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_in_time_order() {
+        let mut response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("replay"))
+                .capture_images(false)
+                .coalesce_moves(0.0, Duration::ZERO),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0),
+                Tick::at(20, 0),
+                Tick::at(30, 0).pressed(1),
+            ],
+        )
+        .await;
+        // Spread the events out, with the second move recorded out of order
+        for (event, time_ms) in response.moves.iter_mut().zip([0.0, 30.0, 10.0]) {
+            event.process_time_ms = time_ms;
+        }
+        response.clicks[0].process_time_ms = 20.0;
+
+        let label = |event: &CursorEvent| match event {
+            CursorEvent::Move(event) => format!("move {}", event.screen_x),
+            CursorEvent::Click(event) => format!("click {}", event.screen_x),
+            _ => unreachable!(),
+        };
+        let expected = vec!["move 10", "move 30", "click 30", "move 20"];
+
+        let mut replayed = vec![];
+        let start = Instant::now();
+        response
+            .replay(3.0, |event| replayed.push(label(event)))
+            .await;
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert_eq!(replayed, expected);
+
+        let mut replayed = vec![];
+        response
+            .replay(0.0, |event| replayed.push(label(event)))
+            .await;
+        assert_eq!(replayed, expected);
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn merges_split_recordings() {
+        let (first_dir, second_dir) = (test_dir("merge-first"), test_dir("merge-second"));
+        let first = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, first_dir.clone()),
+            vec![
+                Tick::at(0, 0).image(10),
+                Tick::at(10, 0).image(10),
+                Tick::at(20, 0).image(10).pressed(1),
+            ],
+        )
+        .await;
+        // Recorded separately, so its own cursors also start at id 0
+        let second = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, second_dir.clone()),
+            vec![
+                Tick::at(0, 0).image(200),
+                Tick::at(10, 0).image(200),
+                Tick::at(20, 0).image(10),
+            ],
+        )
+        .await;
+        let offset = first.duration.as_secs_f64() * 1000.0;
+        let second_first_move = second.moves[0].process_time_ms;
+
+        let merged = first.merge(second, offset);
+
+        // The second recording's copy of the first cursor is merged, and its own cursor 0
+        // is moved past every id either recording used
+        assert_eq!(merged.cursors.len(), 2);
+        assert_eq!(merged.next_cursor_id, 3);
+        let ids: Vec<_> = merged.moves.iter().map(|m| m.cursor_id.as_str()).collect();
+        assert_eq!(ids, ["0", "0", "2", "0"]);
+        assert_eq!(merged.moves[2].process_time_ms, second_first_move + offset);
+        assert!(merged
+            .moves
+            .windows(2)
+            .all(|pair| pair[0].process_time_ms <= pair[1].process_time_ms));
+        assert_eq!(merged.clicks.len(), 1);
+
+        std::fs::remove_dir_all(first_dir).unwrap();
+        std::fs::remove_dir_all(second_dir).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn reloads_cursors_after_a_restart() {
+        let dir = test_dir("reload-cursors");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![
+                Tick::at(0, 0).image(10),
+                Tick::at(1, 0).image(200).kind(CursorKind::IBeam).frames(2),
+            ],
+        )
+        .await;
+
+        let loaded = load_cursors_from_dir(&dir).unwrap();
+        assert_eq!(loaded.len(), response.cursors.len());
+        for (hash, cursor) in &response.cursors {
+            let reloaded = &loaded[hash];
+            assert_eq!(reloaded.id, cursor.id);
+            assert_eq!(reloaded.kind, cursor.kind);
+            assert_eq!(reloaded.hotspot_px, cursor.hotspot_px);
+            assert_eq!(
+                reloaded.animation.as_ref().map(|a| &a.frame_files),
+                cursor.animation.as_ref().map(|a| &a.frame_files)
+            );
+        }
+
+        // Resuming from them writes nothing new for cursors already seen
+        let next = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()).prev_cursors(loaded, 0),
+            vec![Tick::at(0, 0).image(10), Tick::at(1, 0).image(30)],
+        )
+        .await;
+        assert_eq!(next.cursor_files_written, 1);
+        assert_eq!(next.next_cursor_id, response.next_cursor_id + 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn persists_hotspots_and_sizes() {
+        let dir = test_dir("persist-hotspots");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![
+                Tick::at(0, 0).image(10).hotspot(0.25, 0.5),
+                Tick::at(1, 0).image(10).hotspot(0.25, 0.5),
+                Tick::at(2, 0).image(200).hotspot(0.875, 0.125),
+            ],
+        )
+        .await;
+        assert_eq!(response.cursors.len(), 2);
+
+        // Only the files and the sidecar survive the process
+        let loaded = load_cursors_from_dir(&dir).unwrap();
+        for (hash, cursor) in &response.cursors {
+            let reloaded = &loaded[hash];
+            assert_eq!(reloaded.hotspot, cursor.hotspot);
+            assert_eq!(reloaded.hotspot_px, cursor.hotspot_px);
+            assert_eq!(reloaded.resolution, cursor.resolution);
+            assert_eq!(reloaded.screen_size, cursor.screen_size);
+        }
+        let hotspots: HashSet<_> = loaded
+            .values()
+            .map(|cursor| (cursor.hotspot_px.x, cursor.hotspot_px.y))
+            .collect();
+        assert_eq!(hotspots, HashSet::from([(2, 4), (7, 1)]));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn writes_flickering_cursor_once() {
+        let dir = test_dir("flicker");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![
+                Tick::at(0, 0).image(10),
+                Tick::at(1, 0).image(10),
+                Tick::at(2, 0).image(200),
+                Tick::at(3, 0).image(10),
+                Tick::at(4, 0).image(200),
+                Tick::at(5, 0).image(10),
+            ],
+        )
+        .await;
+
+        assert_eq!(response.cursor_files_written, 2);
+        // Both images and the sidecar listing them
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+
+        // Cursors from the previous segment are reused without writing anything
+        let next = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone())
+                .prev_cursors(response.cursors, response.next_cursor_id),
+            vec![Tick::at(0, 0).image(10), Tick::at(1, 0).image(200)],
+        )
+        .await;
+
+        assert_eq!(next.cursor_files_written, 0);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn decimate_keeps_clicks_and_corners() {
+        let mv = |time: f64, x: f64, y: f64| CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "default".to_string(),
+            process_time_ms: time,
+            unix_time_ms: time,
+            x,
+            y,
+            screen_x: 0,
+            screen_y: 0,
+            monitor: Some(0),
+            monitor_id: Some(0),
+            device_id: 0,
+            visible: true,
+            off_screen: false,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            acceleration_x: 0.0,
+            acceleration_y: 0.0,
+            delta: None,
+        };
+
+        // 100Hz along a straight line, then turning sharply at 500ms
+        let mut response = CursorActorResponse {
+            moves: (0..100)
+                .map(|i| {
+                    let time = i as f64 * 10.0;
+                    if i <= 50 {
+                        mv(time, i as f64 / 100.0, 0.0)
+                    } else {
+                        mv(time, 0.5, (i - 50) as f64 / 100.0)
+                    }
+                })
+                .collect(),
+            clicks: vec![CursorClickEvent {
+                active_modifiers: vec![],
+                cursor_num: 1,
+                button: Some(MouseButton::Left),
+                cursor_id: "default".to_string(),
+                process_time_ms: 305.0,
+                unix_time_ms: 305.0,
+                down: true,
+                duration_ms: None,
+                click_count: 1,
+                x: 0.3,
+                y: 0.0,
+                screen_x: 0,
+                screen_y: 0,
+                monitor: Some(0),
+                monitor_id: Some(0),
+                device_id: 0,
+                frame: None,
+            }],
+            ..Default::default()
+        };
+
+        response.decimate(20.0);
+
+        let times: Vec<_> = response.moves.iter().map(|m| m.process_time_ms).collect();
+        assert!(times.len() < 30, "{times:?}");
+        for time in [0.0, 300.0, 310.0, 500.0, 990.0] {
+            assert!(times.contains(&time), "{time} missing from {times:?}");
+        }
+        assert!(times.windows(2).all(|pair| pair[1] - pair[0] <= 50.0));
+        assert_eq!(response.clicks.len(), 1);
+    }
+
+    #[test]
+    fn resamples_to_video_frames() {
+        let mv = |time: f64, x: f64, monitor: u32| CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "default".to_string(),
+            process_time_ms: time,
+            unix_time_ms: 1000.0 + time,
+            x,
+            y: 0.0,
+            screen_x: (x * 100.0) as i32,
+            screen_y: 0,
+            monitor: Some(monitor),
+            monitor_id: Some(monitor),
+            device_id: 0,
+            visible: true,
+            off_screen: false,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            acceleration_x: 0.0,
+            acceleration_y: 0.0,
+            delta: None,
+        };
+
+        // Polled at uneven times, jumping to another monitor at 60ms
+        let mut response = CursorActorResponse {
+            moves: vec![mv(10.0, 0.0, 0), mv(40.0, 0.6, 0), mv(60.0, 0.1, 1)],
+            clicks: vec![CursorClickEvent {
+                active_modifiers: vec![],
+                cursor_num: 1,
+                button: Some(MouseButton::Left),
+                cursor_id: "default".to_string(),
+                process_time_ms: 31.0,
+                unix_time_ms: 1031.0,
+                down: true,
+                duration_ms: None,
+                click_count: 1,
+                x: 0.4,
+                y: 0.0,
+                screen_x: 40,
+                screen_y: 0,
+                monitor: Some(0),
+                monitor_id: Some(0),
+                device_id: 0,
+                frame: None,
+            }],
+            duration: Duration::from_millis(100),
+            ..Default::default()
+        };
+
+        response.resample_to_frames(50.0);
+
+        let frames: Vec<_> = response
+            .moves
+            .iter()
+            .map(|m| {
+                (
+                    m.process_time_ms,
+                    m.monitor,
+                    (m.x * 1000.0).round() / 1000.0,
+                )
+            })
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                (20.0, Some(0), 0.2),
+                (40.0, Some(0), 0.6),
+                (60.0, Some(1), 0.1),
+                (80.0, Some(1), 0.1),
+                (100.0, Some(1), 0.1),
+            ]
+        );
+        assert_eq!(response.moves[0].screen_x, 20);
+        assert_eq!(response.moves[0].unix_time_ms, 1020.0);
+        assert!((response.moves[1].velocity_x - 20.0).abs() < 1e-9);
+        assert_eq!(response.clicks[0].frame, Some(2));
+        assert_eq!(response.clicks[0].process_time_ms, 31.0);
+
+        // Without a usable frame rate, the moves are left as recorded
+        for fps in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let mut response = CursorActorResponse {
+                moves: vec![mv(10.0, 0.0, 0)],
+                duration: Duration::from_millis(100),
+                ..Default::default()
+            };
+            response.resample_to_frames(fps);
+            assert_eq!(response.moves.len(), 1, "{fps}");
+            assert_eq!(response.moves[0].process_time_ms, 10.0, "{fps}");
+        }
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn stale_next_cursor_id_is_bumped() {
+        let dir = test_dir("stale-id");
+        let prev_cursor = |id| Cursor {
+            file_name: Some(format!("cursor_{id}.png")),
+            id,
+            hotspot: XY::new(0.0, 0.0),
+            hotspot_px: XY::new(0, 0),
+            kind: CursorKind::Custom,
+            system_name: None,
+            scale: 1.0,
+            resolution: XY::new(8, 8),
+            screen_size: XY::new(0.08, 0.08),
+            animation: None,
+            possibly_stale: false,
+        };
+        let prev_cursors = Cursors::from([(1, prev_cursor(0)), (2, prev_cursor(5))]);
+
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()).prev_cursors(prev_cursors, 2),
+            vec![Tick::at(0, 0), Tick::at(1, 0).image(10)],
+        )
+        .await;
+
+        assert_eq!(response.moves[0].cursor_id, "6");
+        assert_eq!(response.next_cursor_id, 7);
+        assert!(dir.join("cursor_6.png").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn prunes_unused_cursor_files() {
+        let dir = test_dir("prune");
+        let first = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![Tick::at(0, 0), Tick::at(1, 0).image(10).frames(1)],
+        )
+        .await;
+        std::fs::write(dir.join("cursor_7.png"), b"stale").unwrap();
+        std::fs::write(dir.join("cursor_7_1.png"), b"stale").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"keep").unwrap();
+
+        record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone())
+                .prev_cursors(first.cursors, first.next_cursor_id)
+                .prune_unused_cursors(true),
+            vec![Tick::at(0, 0), Tick::at(1, 0)],
+        )
+        .await;
+
+        assert!(dir.join("cursor_0.png").exists());
+        assert!(dir.join("cursor_0_1.png").exists());
+        assert!(dir.join("notes.txt").exists());
+        assert!(!dir.join("cursor_7.png").exists());
+        assert!(!dir.join("cursor_7_1.png").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cursor_file_names() {
+        assert!(is_cursor_file("cursor_3.png"));
+        assert!(is_cursor_file("cursor_3_2.webp"));
+        assert!(!is_cursor_file("cursor_.png"));
+        assert!(!is_cursor_file("cursor_3.json"));
+        assert!(!is_cursor_file("my_cursor_3.png"));
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn saves_webp_cursors() {
+        let dir = test_dir("webp");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone())
+                .image_format(CursorImageFormat::Webp),
+            vec![Tick::at(0, 0), Tick::at(1, 0).image(10).frames(1)],
+        )
+        .await;
+
+        let cursor = response.cursors.values().next().unwrap();
+        assert_eq!(cursor.file_name.as_deref(), Some("cursor_0.webp"));
+        let animation = cursor.animation.as_ref().unwrap();
+        assert_eq!(
+            animation.frame_files,
+            vec!["cursor_0.webp", "cursor_0_1.webp"]
+        );
+        for file_name in &animation.frame_files {
+            let image = image::open(dir.join(file_name)).unwrap();
+            assert_eq!(image.dimensions(), (8, 8));
+        }
+
+        // Transparency survives the conversion
+        let translucent = encode_png(&image::RgbaImage::from_pixel(
+            4,
+            4,
+            image::Rgba([255, 0, 0, 100]),
+        ))
+        .unwrap();
+        let path = dir.join("translucent.webp");
+        save_cursor_image(&translucent, &path).unwrap();
+        let image = image::open(&path).unwrap().into_rgba8();
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 100]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn merges_similar_cursors() {
+        let ticks = vec![
+            Tick::at(0, 0),
+            Tick::at(1, 0).image(100),
+            Tick::at(2, 0).image(100).speck(),
+            Tick::at(3, 0).image(200),
+            Tick::at(4, 0).image(100).speck(),
+        ];
+
+        let dir = test_dir("similar");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()).merge_similar_cursors(4),
+            ticks.clone(),
+        )
+        .await;
+
+        assert_eq!(response.cursors.len(), 2);
+        let ids: Vec<_> = response
+            .moves
+            .iter()
+            .map(|m| m.cursor_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["0", "0", "1", "0"]);
+        std::fs::remove_dir_all(dir).unwrap();
+
+        // Only identical images are merged by default
+        let dir = test_dir("exact");
+        let response = record(CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()), ticks).await;
+        assert_eq!(response.cursors.len(), 3);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn saves_animation_frames() {
+        let dir = test_dir("animated");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(1, 0).image(10).frames(2),
+                Tick::at(2, 0).image(200),
+            ],
+        )
+        .await;
+
+        let animated = response.cursors.values().find(|c| c.id == 0).unwrap();
+        let animation = animated.animation.as_ref().unwrap();
+        assert_eq!(
+            animation.frame_files,
+            vec!["cursor_0.png", "cursor_0_1.png", "cursor_0_2.png"]
+        );
+        assert_eq!(animation.frame_duration_ms, 50.0);
+        for file_name in &animation.frame_files {
+            assert!(dir.join(file_name).exists());
+        }
+
+        let still = response.cursors.values().find(|c| c.id == 1).unwrap();
+        assert!(still.animation.is_none());
+
+        let cap_project::Cursors::Correct(meta) =
+            project_cursor_meta(&response.cursors, RelativePath::new("cursors"))
+        else {
+            panic!("cursor meta built in the legacy format");
+        };
+        let frame_paths = &meta["0"].animation.as_ref().unwrap().frame_paths;
+        assert_eq!(frame_paths[2], RelativePath::new("cursors/cursor_0_2.png"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn keeps_images_in_memory() {
+        let dir = test_dir("in-memory");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()).keep_images_in_memory(true),
+            vec![
+                Tick::at(0, 0),
+                Tick::at(1, 0).image(10).frames(1),
+                Tick::at(2, 0).image(200),
+            ],
+        )
+        .await;
+
+        assert!(!dir.exists());
+        assert_eq!(response.cursors.len(), 2);
+        for cursor in response.cursors.values() {
+            assert!(cursor.file_name.is_none());
+            assert!(cursor.animation.is_none());
+            let image = image::load_from_memory(&response.cursor_images[&cursor.id]).unwrap();
+            assert_eq!(image.dimensions(), (8, 8));
+        }
+
+        let cap_project::Cursors::Correct(meta) =
+            project_cursor_meta(&response.cursors, RelativePath::new("cursors"))
+        else {
+            panic!("cursor meta built in the legacy format");
+        };
+        assert!(meta.is_empty());
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn unwritable_cursors_dir_still_records() {
+        let file = test_dir("unwritable");
+        std::fs::write(&file, []).unwrap();
+
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, file.join("cursors")),
+            vec![Tick::at(0, 0).image(10), Tick::at(50, 50).image(10)],
+        )
+        .await;
+
+        assert!(response.image_capture_disabled);
+        assert!(response.cursors.is_empty());
+        assert_eq!(response.moves.len(), 1);
+        assert_eq!(response.moves[0].cursor_id, "default");
+
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[cfg(not(feature = "cursor-images"))]
+    #[tokio::test]
+    async fn records_default_cursor_without_image_support() {
+        let dir = test_dir("no-images");
+        let response = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![Tick::at(0, 0), Tick::at(1, 0).image(10), Tick::at(2, 0)],
+        )
+        .await;
+
+        assert!(response.cursors.is_empty());
+        assert!(response.moves.iter().all(|m| m.cursor_id == "default"));
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn stop_timeout_keeps_recorded_events() {
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("hung")).capture_images(false);
+        let (actor, done) = start(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0),
+                Tick::at(20, 0).hang(Duration::from_secs(1)),
+            ],
+        );
+        done.await.unwrap();
+
+        // A worker stuck in a blocking call can stall the runtime's timers, so the
+        // timeout runs on a runtime of its own
+        let started = Instant::now();
+        let response = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap()
+                .block_on(actor.stop_timeout(Duration::from_millis(50)))
+        })
+        .join()
+        .unwrap()
+        .unwrap();
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        let moves: Vec<_> = response.moves.iter().map(|m| m.screen_x).collect();
+        assert_eq!(moves, vec![10]);
+    }
+
+    #[tokio::test]
+    async fn times_from_shared_start() {
+        let start_time = Instant::now() - Duration::from_secs(10);
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("start"))
+            .capture_images(false)
+            .start_time(start_time);
+        let response = record(config, vec![Tick::at(0, 0), Tick::at(10, 0)]).await;
+
+        assert_eq!(response.moves.len(), 1);
+        assert!(response.moves[0].process_time_ms >= 10_000.0);
+    }
+
+    #[tokio::test]
+    async fn shifts_times_by_offset() {
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("offset"))
+            .capture_images(false)
+            .time_offset_ms(500.0);
+        let response = record(
+            config,
+            vec![Tick::at(0, 0), Tick::at(10, 0), Tick::at(20, 0).pressed(1)],
+        )
+        .await;
+
+        let start = response.start.unwrap();
+        let end = response.end.unwrap();
+        assert!(start.process_time_ms >= 500.0);
+        assert!(response.moves[0].process_time_ms > start.process_time_ms);
+        assert!(response.clicks[0].process_time_ms >= response.moves[1].process_time_ms);
+        assert!(end.process_time_ms >= 500.0 + response.duration.as_secs_f64() * 1000.0 - 1e-6);
+        assert!(response.duration < Duration::from_millis(500));
+
+        let res = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("offset"))
+            .time_offset_ms(f64::NAN)
+            .spawn_with_source(SystemCursorSource::default());
+        assert!(matches!(res, Err(CursorRecordError::InvalidTimeOffset(_))));
+    }
+
+    #[tokio::test]
+    async fn lagging_stream_keeps_buffered_events() {
+        let (tx, _rx) = mpsc::channel(1);
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("lagging"))
+            .capture_images(false)
+            .events(tx);
+        let response = record(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0),
+                Tick::at(20, 0),
+                Tick::at(30, 0),
+            ],
+        )
+        .await;
+
+        assert_eq!(response.moves.len(), 3);
+        assert_eq!(response.dropped_stream_events, 2);
+    }
+
+    #[tokio::test]
+    async fn records_modifier_timeline() {
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("modifiers"))
+            .capture_images(false)
+            .record_modifiers(true);
+        let response = record(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(0, 0).keys(&[Keycode::LShift]),
+                // Switching sides keeps Shift held
+                Tick::at(0, 0).keys(&[Keycode::RShift, Keycode::LControl]),
+                // Still tracked while the cursor is off screen
+                Tick::at(-50, 0).keys(&[Keycode::A]),
+            ],
+        )
+        .await;
+
+        let modifiers: Vec<_> = response
+            .modifier_events
+            .iter()
+            .map(|event| (event.modifier.as_str(), event.down))
+            .collect();
+        assert_eq!(
+            modifiers,
+            [
+                ("Shift", true),
+                ("Ctrl", true),
+                ("Ctrl", false),
+                ("Shift", false)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn modifier_timeline_is_opt_in() {
+        let config =
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("no-modifiers")).capture_images(false);
+        let response = record(
+            config,
+            vec![Tick::at(0, 0), Tick::at(0, 0).keys(&[Keycode::LShift])],
+        )
+        .await;
+
+        assert!(response.modifier_events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn tags_events_with_device() {
+        let config =
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("devices")).capture_images(false);
+        let response = record(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0).pressed(1),
+                Tick::at(10, 0),
+                // Clicking again with a pen in the same spot isn't a double-click
+                Tick::at(10, 0).pressed(1).device(2),
+                Tick::at(10, 0).device(2),
+                Tick::at(20, 0).device(2),
+            ],
+        )
+        .await;
+
+        let moves: Vec<_> = response.moves.iter().map(|m| m.device_id).collect();
+        assert_eq!(moves, [0, 2]);
+
+        let clicks: Vec<_> = response
+            .clicks
+            .iter()
+            .map(|c| (c.device_id, c.click_count))
+            .collect();
+        assert_eq!(clicks, [(0, 1), (0, 1), (2, 1), (2, 1)]);
+    }
+
+    #[tokio::test]
+    async fn tracks_duration_and_samples() {
+        let config =
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("duration")).capture_images(false);
+        let response = record(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0),
+                Tick::at(20, 0),
+                Tick::at(30, 0),
+            ],
+        )
+        .await;
+
+        // Sampling carries on until the recorder sees the stop
+        assert!(response.sample_count >= 3);
+        let last_move = response.moves.last().unwrap().process_time_ms;
+        assert!(response.duration.as_secs_f64() * 1000.0 >= last_move);
+        assert_eq!(
+            response.end.unwrap().process_time_ms,
+            response.duration.as_secs_f64() * 1000.0
+        );
+    }
+
+    #[tokio::test]
+    async fn backs_off_polling_while_idle() {
+        let idle_interval = Duration::from_millis(5);
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("adaptive"))
+            .capture_images(false)
+            .adaptive_polling(idle_interval, 2);
+        let response = record(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0),
+                Tick::at(10, 0),
+                Tick::at(10, 0),
+                Tick::at(10, 0),
+                Tick::at(20, 0),
+            ],
+        )
+        .await;
+
+        let changes: Vec<_> = response
+            .poll_interval_changes
+            .iter()
+            .map(|change| change.interval)
+            .collect();
+        assert_eq!(changes[..2], [idle_interval, Duration::from_millis(1)]);
+
+        // Back to fast polling on the very tick the cursor moved again
+        assert_eq!(
+            response.poll_interval_changes[1].process_time_ms,
+            response.moves[1].process_time_ms
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_itself_after_idling() {
+        let idle = Duration::from_millis(30);
+        // Moves over the first 40 ticks keep pushing the deadline back
+        let (actor, _done) = start(
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("auto-stop"))
+                .capture_images(false)
+                .auto_stop_after_idle(idle),
+            (0..40).map(|i| Tick::at(i, 0)).collect(),
+        );
+
+        let started = Instant::now();
+        while !actor.is_stopped() {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "recorder didn't stop itself"
+            );
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let response = actor.stop().await.unwrap();
+        assert!(response.auto_stopped);
+        assert_eq!(response.moves.len(), 39);
+        // Recording outlasted a single idle period since the start
+        let idle_ms = idle.as_secs_f64() * 1000.0;
+        let last_move = response.moves.last().unwrap().process_time_ms;
+        assert!(last_move > idle_ms);
+        assert!(response.end.unwrap().process_time_ms - last_move >= idle_ms);
+    }
+
+    #[test]
+    fn rejects_zero_idle_interval() {
+        let res = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("zero-idle"))
+            .adaptive_polling(Duration::ZERO, 10)
+            .spawn();
+        assert!(matches!(res, Err(CursorRecordError::ZeroPollInterval)));
+    }
+
+    #[test]
+    fn rejects_invalid_cursor_resolution() {
+        for size in [0, i32::MAX as u32 + 1] {
+            let res = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("cursor-resolution"))
+                .cursor_resolution(size)
+                .spawn();
+            assert!(matches!(
+                res,
+                Err(CursorRecordError::InvalidCursorResolution(s)) if s == size
+            ));
+        }
+    }
+
+    #[test]
+    fn records_without_a_runtime() {
+        let (done_tx, done_rx) = oneshot::channel();
+        let source = ScriptedSource {
+            current: Tick::at(0, 0),
+            ticks: vec![Tick::at(0, 0), Tick::at(10, 0).pressed(1), Tick::at(10, 0)].into(),
+            done: Some(done_tx),
+        };
+
+        let actor = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("blocking"))
+            .capture_images(false)
+            .poll_interval(Duration::from_millis(1))
+            .spawn_blocking_with_source(source)
+            .unwrap();
+        done_rx.blocking_recv().unwrap();
+        let response = actor.stop().unwrap();
+
+        assert_eq!(response.moves.len(), 1);
+        assert_eq!(response.clicks.len(), 2);
+    }
+
+    #[test]
+    fn blocking_stop_reports_panics() {
+        let source = ScriptedSource {
+            current: Tick::at(0, 0),
+            ticks: vec![Tick::at(0, 0), Tick::at(10, 0), Tick::at(20, 0).panic()].into(),
+            done: None,
+        };
+
+        let actor = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("blocking-panic"))
+            .capture_images(false)
+            .poll_interval(Duration::from_millis(1))
+            .spawn_blocking_with_source(source)
+            .unwrap();
+        while !actor.thread.is_finished() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        match actor.stop() {
+            Err(CursorRecordError::Panicked(message)) => assert_eq!(message, "scripted panic"),
+            res => panic!("expected a panic, got {:?}", res.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn blocking_spawn_reports_invalid_config() {
+        let res = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("blocking-invalid"))
+            .poll_interval(Duration::ZERO)
+            .spawn_blocking();
+        assert!(matches!(res, Err(CursorRecordError::ZeroPollInterval)));
+    }
+
+    #[tokio::test]
+    async fn counts_overruns() {
+        let config =
+            CursorRecorderConfig::new(TEST_BOUNDS, test_dir("overruns")).capture_images(false);
+        let response = record(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0).hang(Duration::from_millis(20)),
+            ],
+        )
+        .await;
+
+        assert!(response.overruns >= 1);
+    }
+
+    #[tokio::test]
+    async fn filters_events() {
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("filter"))
+            .capture_images(false)
+            .filter(|event| !matches!(event, CursorEvent::Move(_)));
+        let response = record(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0).pressed(1),
+                Tick::at(20, 0).pressed(1),
+                Tick::at(20, 0),
+            ],
+        )
+        .await;
+
+        assert!(response.moves.is_empty());
+        assert_eq!(response.clicks.len(), 2);
+        assert_eq!(response.drags.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn marks_start_and_end() {
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("markers"))
+            .capture_images(false)
+            .drop_out_of_bounds(true);
+        let response = record(
+            config,
+            vec![Tick::at(20, 40), Tick::at(20, 40), Tick::at(150, 50)],
+        )
+        .await;
+
+        // Neither position was recorded as a move, the first as it never changed and the
+        // last as it's out of bounds
+        assert!(response.moves.is_empty());
+
+        let start = response.start.unwrap();
+        assert_eq!((start.x, start.y, start.monitor), (0.2, 0.4, Some(0)));
+        assert!(start.visible);
+        assert!(start.process_time_ms < 1000.0);
+
+        let end = response.end.unwrap();
+        assert_eq!((end.x, end.y, end.monitor), (1.0, 0.5, None));
+        assert!(end.process_time_ms >= start.process_time_ms);
+    }
+
+    #[tokio::test]
+    async fn spilled_events_are_restored_in_order() {
+        let spill_path = test_dir("spill");
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("spill-cursors"))
+            .capture_images(false)
+            .spill_to_disk(spill_path.clone(), 2);
+        let response = record(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0),
+                Tick::at(20, 0).pressed(1),
+                Tick::at(30, 0),
+                Tick::at(40, 0),
+                Tick::at(50, 0),
+            ],
+        )
+        .await;
+
+        let moves: Vec<_> = response.moves.iter().map(|m| m.x).collect();
+        assert_eq!(moves, vec![0.1, 0.2, 0.3, 0.4, 0.5]);
+        assert_eq!(response.clicks.len(), 2);
+        assert!(response.clicks[0].down && !response.clicks[1].down);
+        assert!(!spill_path.exists());
+    }
+
+    // The source hangs the worker it runs on, and the timeout needs another
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn stop_timeout_keeps_the_spill_file() {
+        let spill_path = test_dir("spill-timeout");
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("spill-timeout-cursors"))
+            .capture_images(false)
+            .spill_to_disk(spill_path.clone(), 2);
+        let (actor, done) = start(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0),
+                Tick::at(20, 0).pressed(1),
+                Tick::at(30, 0),
+                Tick::at(40, 0).hang(Duration::from_millis(300)),
+            ],
+        );
+        done.await.unwrap();
+
+        let mut stats = actor.stats();
+        let response = actor.stop_timeout(Duration::from_millis(20)).await.unwrap();
+        // The stats sender goes away once the recorder has wrapped up
+        while stats.changed().await.is_ok() {}
+
+        let spilled = std::fs::read_to_string(&spill_path).unwrap();
+        let spilled_moves: usize = spilled
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<CursorEvents>(line)
+                    .unwrap()
+                    .moves
+                    .len()
+            })
+            .sum();
+        assert_eq!(spilled_moves + response.moves.len(), 4);
+
+        std::fs::remove_file(spill_path).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    // The source hangs the worker it runs on, and the timeout needs another
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn stop_timeout_keeps_the_sidecar() {
+        let dir = test_dir("sidecar-timeout");
+        let prev = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![
+                Tick::at(0, 0).image(10).hotspot(0.25, 0.5),
+                Tick::at(1, 0).image(10).hotspot(0.25, 0.5),
+            ],
+        )
+        .await;
+        assert_eq!(prev.cursors.len(), 1);
+
+        let (actor, done) = start(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone())
+                .prev_cursors(prev.cursors, prev.next_cursor_id),
+            vec![
+                Tick::at(0, 0).image(10).hotspot(0.25, 0.5),
+                Tick::at(1, 0).image(200),
+                // The new cursor's save finishes while the recorder hangs here
+                Tick::at(2, 0).image(200).hang(Duration::from_millis(300)),
+            ],
+        );
+        done.await.unwrap();
+
+        let mut stats = actor.stats();
+        actor.stop_timeout(Duration::from_millis(20)).await.unwrap();
+        while stats.changed().await.is_ok() {}
+
+        let loaded = load_cursors_from_dir(&dir).unwrap();
+        let first = loaded.values().find(|cursor| cursor.id == 0).unwrap();
+        assert_eq!(first.hotspot, XY::new(0.25, 0.5));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[tokio::test]
+    async fn abort_discards_recording() {
+        let dir = test_dir("abort");
+        let spill_path = test_dir("abort-spill");
+
+        // A cursor from an earlier segment, whose file has to survive
+        let prev = record(
+            CursorRecorderConfig::new(TEST_BOUNDS, dir.clone()),
+            vec![Tick::at(0, 0), Tick::at(1, 0).image(10)],
+        )
+        .await;
+
+        // The recorder runs on this thread, so this sees everything it logs
+        let errors = Arc::new(AtomicUsize::new(0));
+        let _subscriber = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(ErrorCount(errors.clone())),
+        );
+
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, dir.clone())
+            .prev_cursors(prev.cursors, prev.next_cursor_id)
+            .spill_to_disk(spill_path.clone(), 2);
+        let (actor, done) = start(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0).image(10),
+                Tick::at(20, 0).image(200).frames(1),
+                Tick::at(30, 0).image(200).pressed(1),
+                Tick::at(40, 0).image(200),
+            ],
+        );
+        done.await.unwrap();
+        let recorded = actor.recorded.clone();
+        actor.abort(true).await.unwrap();
+
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["cursor_0.png", CURSOR_SIDECAR_FILE]);
+        // Each of the animated cursor's files was removed once
+        assert_eq!(errors.load(std::sync::atomic::Ordering::Relaxed), 0);
+        // The sidecar no longer lists the deleted cursors
+        assert_eq!(load_cursors_from_dir(&dir).unwrap().len(), 1);
+        assert!(!spill_path.exists());
+        assert!(lock(&recorded).moves.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_live_stats() {
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("stats"))
+            .capture_images(false)
+            .filter(|event| !matches!(event, CursorEvent::Click(click) if !click.down));
+        let (actor, done) = start(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0),
+                Tick::at(20, 0).pressed(1),
+                Tick::at(30, 0),
+            ],
+        );
+        let stats = actor.stats();
+        assert_eq!(*stats.borrow(), CursorStats::default());
+
+        done.await.unwrap();
+        let response = actor.stop().await.unwrap();
+
+        let stats = *stats.borrow();
+        assert_eq!((stats.moves, stats.clicks, stats.cursors), (3, 1, 0));
+        assert_eq!(stats.samples, response.sample_count);
+        assert_eq!(stats.overruns, response.overruns);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn journal_recovers_crashed_recording() {
+        let journal_path = test_dir("journal");
+        let config = CursorRecorderConfig::new(TEST_BOUNDS, test_dir("journal-cursors"))
+            .capture_images(false)
+            .journal(journal_path.clone(), Duration::ZERO);
+        // Hanging stands in for a crash, as nothing gets past it until it's over
+        let (actor, done) = start(
+            config,
+            vec![
+                Tick::at(0, 0),
+                Tick::at(10, 0),
+                Tick::at(20, 0).pressed(1),
+                Tick::at(30, 0).hang(Duration::from_millis(500)),
+            ],
+        );
+        done.await.unwrap();
+
+        let recovered = recover_journal(&journal_path).unwrap();
+        let moves: Vec<_> = recovered.moves.iter().map(|m| m.screen_x).collect();
+        assert_eq!(moves, vec![10, 20]);
+        assert_eq!(recovered.clicks.len(), 1);
+
+        // A flush cut short by the crash is skipped
+        let mut torn = std::fs::read_to_string(&journal_path).unwrap();
+        torn.push_str("{\"moves\":[{\"active_");
+        let torn_path = test_dir("journal-torn");
+        std::fs::write(&torn_path, torn).unwrap();
+        assert_eq!(recover_journal(&torn_path).unwrap().moves.len(), 2);
+        std::fs::remove_file(torn_path).unwrap();
+
+        let response = actor.stop().await.unwrap();
+        assert_eq!(response.moves.len(), 3);
+        assert!(!journal_path.exists());
+    }
+
+    #[test]
+    fn velocity_between_samples() {
+        let mut motion = Motion::default();
+
+        assert_eq!(
+            motion.sample(0.0, 0.0, Some(0), 0.0),
+            (XY::new(0.0, 0.0), XY::new(0.0, 0.0))
+        );
+
+        let (velocity, acceleration) = motion.sample(0.1, 0.0, Some(0), 100.0);
+        assert!((velocity.x - 1.0).abs() < 1e-9);
+        assert!((acceleration.x - 10.0).abs() < 1e-9);
+
+        // Two samples sharing a timestamp mustn't divide by zero
+        let (velocity, acceleration) = motion.sample(0.2, 0.0, Some(0), 100.0);
+        assert_eq!((velocity.x, acceleration.x), (0.0, 0.0));
+
+        // Nor can velocity be measured across monitors
+        let (velocity, _) = motion.sample(0.5, 0.5, Some(1), 200.0);
+        assert_eq!(velocity, XY::new(0.0, 0.0));
+    }
+}
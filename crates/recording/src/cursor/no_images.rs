@@ -0,0 +1,43 @@
+//! Stand-ins for [`super::images`] when built without the `cursor-images` feature. No
+//! image is ever captured then, so these only keep the recorder compiling.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use super::{CursorAnimation, CursorData};
+
+#[derive(Error, Debug)]
+pub enum ImageError {
+    #[error("built without the cursor-images feature")]
+    Unsupported,
+}
+
+pub fn image_dimensions(_image: &[u8]) -> Option<(u32, u32)> {
+    None
+}
+
+pub fn save_cursor_images(
+    _data: &CursorData,
+    _path: &Path,
+    _animation: Option<&CursorAnimation>,
+    _dir: &Path,
+) -> Result<(u32, u32), ImageError> {
+    Err(ImageError::Unsupported)
+}
+
+pub fn normalize_cursor_size(_data: &mut CursorData, _size: (u32, u32)) {}
+
+pub struct SimilarCursors;
+
+impl SimilarCursors {
+    pub fn new(_max_distance: u32) -> Self {
+        Self
+    }
+
+    pub fn find(&mut self, _hash: u64, _image: &[u8]) -> Option<u32> {
+        None
+    }
+
+    pub fn insert(&mut self, _hash: u64, _image: &[u8], _id: u32) {}
+}
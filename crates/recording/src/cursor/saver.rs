@@ -0,0 +1,204 @@
+use std::{collections::VecDeque, path::PathBuf, sync::Arc};
+
+use cap_media::platform::Bounds;
+use cap_project::XY;
+use tokio::task::{JoinError, JoinSet};
+
+use super::{
+    hotspot_pixels, image_dimensions, normalize_cursor_size, position_scale, save_cursor_images,
+    screen_size, Cursor, CursorAnimation, CursorData, CursorImageFormat, ImageError,
+};
+
+/// How many cursors are prepared and written at once. Bursts of new cursors queue up
+/// behind these instead of each taking a blocking thread and hitting the disk together.
+const MAX_CONCURRENT_SAVES: usize = 2;
+
+/// Everything about a new cursor other than its image that's needed to describe it.
+#[derive(Clone)]
+pub struct SaveOptions {
+    pub cursors_dir: PathBuf,
+    pub keep_images_in_memory: bool,
+    pub retain_saved_images: bool,
+    pub image_format: CursorImageFormat,
+    pub normalize_size: Option<(u32, u32)>,
+    pub first_monitor: Bounds,
+}
+
+struct NewCursor {
+    hash: u64,
+    id: u32,
+    data: CursorData,
+}
+
+pub struct CursorSave {
+    pub hash: u64,
+    pub cursor: Cursor,
+    /// The image, when it's kept in memory instead of being saved or as well.
+    pub image: Option<Vec<u8>>,
+    pub res: Result<(u32, u32), ImageError>,
+}
+
+/// Turns newly seen cursor images into [`Cursor`]s off the polling loop: resizing,
+/// measuring and saving them all happen on the blocking pool, a few at a time, so the
+/// loop only hashes images and looks them up. Finished saves are handed back in the
+/// order they complete.
+pub struct CursorSaver {
+    options: Arc<SaveOptions>,
+    queued: VecDeque<NewCursor>,
+    running: JoinSet<CursorSave>,
+}
+
+impl CursorSaver {
+    pub fn new(options: SaveOptions) -> Self {
+        Self {
+            options: Arc::new(options),
+            queued: VecDeque::new(),
+            running: JoinSet::new(),
+        }
+    }
+
+    pub fn push(&mut self, hash: u64, id: u32, data: CursorData) {
+        self.queued.push_back(NewCursor { hash, id, data });
+        self.start_queued();
+    }
+
+    /// Sizes cursors that haven't started saving yet against `bounds`, including any
+    /// still queued from before. Saves already running keep the bounds they started with.
+    pub fn set_first_monitor(&mut self, bounds: Bounds) {
+        Arc::make_mut(&mut self.options).first_monitor = bounds;
+    }
+
+    /// A save that has finished, if any, without waiting.
+    pub fn try_finished(&mut self) -> Option<Result<CursorSave, JoinError>> {
+        let res = self.running.try_join_next();
+        self.start_queued();
+        res
+    }
+
+    /// Waits for the next save to finish, or returns `None` once nothing is left.
+    pub async fn finished(&mut self) -> Option<Result<CursorSave, JoinError>> {
+        self.start_queued();
+        let res = self.running.join_next().await;
+        self.start_queued();
+        res
+    }
+
+    /// Drops the cursors that are still waiting for their turn. Saves already running
+    /// still finish.
+    pub fn discard_queued(&mut self) {
+        self.queued.clear();
+    }
+
+    fn start_queued(&mut self) {
+        while self.running.len() < MAX_CONCURRENT_SAVES {
+            let Some(new_cursor) = self.queued.pop_front() else {
+                return;
+            };
+
+            let options = self.options.clone();
+            self.running
+                .spawn_blocking(move || save_new_cursor(new_cursor, &options));
+        }
+    }
+}
+
+fn save_new_cursor(new_cursor: NewCursor, options: &SaveOptions) -> CursorSave {
+    let NewCursor { hash, id, mut data } = new_cursor;
+
+    // Only done for new cursors, as lookups use the image as captured
+    if let Some(size) = options.normalize_size {
+        normalize_cursor_size(&mut data, size);
+    }
+
+    let on_disk = !options.keep_images_in_memory;
+    let resolution = image_dimensions(&data.image)
+        .map_or(XY::new(0, 0), |(width, height)| XY::new(width, height));
+    let cursor = Cursor {
+        file_name: on_disk.then(|| format!("cursor_{id}.{}", options.image_format.extension())),
+        id,
+        hotspot: data.hotspot,
+        hotspot_px: hotspot_pixels(data.hotspot, resolution),
+        kind: data.kind,
+        system_name: data.system_name.clone(),
+        scale: data.scale,
+        resolution,
+        screen_size: screen_size(
+            resolution,
+            data.scale,
+            position_scale(),
+            &options.first_monitor,
+        ),
+        animation: CursorAnimation::new(id, &data, options.image_format).filter(|_| on_disk),
+        possibly_stale: false,
+    };
+
+    let Some(file_name) = &cursor.file_name else {
+        return CursorSave {
+            hash,
+            cursor,
+            image: Some(data.image),
+            res: Ok((resolution.x, resolution.y)),
+        };
+    };
+
+    let res = save_cursor_images(
+        &data,
+        &options.cursors_dir.join(file_name),
+        cursor.animation.as_ref(),
+        &options.cursors_dir,
+    );
+    CursorSave {
+        hash,
+        cursor,
+        image: options.retain_saved_images.then_some(data.image),
+        res,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cursor::CursorKind;
+
+    #[tokio::test]
+    async fn bounds_concurrent_saves() {
+        let mut saver = CursorSaver::new(SaveOptions {
+            cursors_dir: PathBuf::new(),
+            keep_images_in_memory: true,
+            retain_saved_images: false,
+            image_format: CursorImageFormat::Png,
+            normalize_size: None,
+            first_monitor: Bounds {
+                x: 0.0,
+                y: 0.0,
+                width: 100.0,
+                height: 100.0,
+            },
+        });
+
+        for id in 0..5 {
+            saver.push(
+                id as u64,
+                id,
+                CursorData {
+                    image: vec![],
+                    hotspot: XY::new(0.0, 0.0),
+                    kind: CursorKind::Custom,
+                    system_name: None,
+                    scale: 1.0,
+                    frames: vec![],
+                    frame_duration_ms: 0.0,
+                },
+            );
+        }
+        assert_eq!(saver.running.len(), MAX_CONCURRENT_SAVES);
+        assert_eq!(saver.queued.len(), 5 - MAX_CONCURRENT_SAVES);
+
+        let mut ids = vec![];
+        while let Some(save) = saver.finished().await {
+            ids.push(save.unwrap().cursor.id);
+        }
+        ids.sort();
+        assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+    }
+}
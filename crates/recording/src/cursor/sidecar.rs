@@ -0,0 +1,275 @@
+use std::{collections::HashMap, io::ErrorKind, path::Path};
+
+use cap_project::XY;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::{
+    cursor_image_hash, image_dimensions, Cursor, CursorAnimation, CursorKind, Cursors,
+    CURSOR_HASH_VERSION,
+};
+
+/// Name of the file in the cursors directory describing the images saved next to it.
+pub const CURSOR_SIDECAR_FILE: &str = "cursors.json";
+
+/// Contents of [`CURSOR_SIDECAR_FILE`], rewritten whenever a new cursor image has been
+/// saved and once more when recording stops, so it lists every cursor whose file is in
+/// the directory, carried over ones included. As JSON:
+///
+/// ```json
+/// {
+///   "hashVersion": 1,
+///   "cursors": [{
+///     "id": 0,
+///     "hash": 1234567890,
+///     "fileName": "cursor_0.png",
+///     "hotspot": { "x": 0.25, "y": 0.1 },
+///     "hotspotPx": { "x": 8, "y": 3 },
+///     "resolution": { "x": 32, "y": 32 },
+///     "screenSize": { "x": 0.0125, "y": 0.022 },
+///     "scale": 1.0,
+///     "kind": "arrow",
+///     "systemName": null,
+///     "animation": { "frameFiles": ["cursor_0.png", "cursor_0_1.png"], "frameDurationMs": 50.0 }
+///   }]
+/// }
+/// ```
+///
+/// `hash` is the [`cursor_image_hash`] of the image as it was captured, which only
+/// matches the file's own bytes when it was saved as-is, and is only trusted when
+/// `hashVersion` is the current [`CURSOR_HASH_VERSION`]. `animation` is left out for
+/// still cursors. The other fields are the [`Cursor`] fields of the same name.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sidecar {
+    hash_version: u32,
+    cursors: Vec<SidecarCursor>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SidecarCursor {
+    id: u32,
+    hash: u64,
+    file_name: String,
+    hotspot: XY<f64>,
+    hotspot_px: XY<u32>,
+    resolution: XY<u32>,
+    screen_size: XY<f64>,
+    scale: f64,
+    kind: CursorKind,
+    system_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    animation: Option<SidecarAnimation>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SidecarAnimation {
+    frame_files: Vec<String>,
+    frame_duration_ms: f64,
+}
+
+/// Writes the sidecar for the cursors saved to `cursors_dir`, replacing the previous one
+/// in a single rename so a crash mid-write leaves the old one intact.
+pub fn write_cursor_sidecar(cursors_dir: &Path, cursors: &Cursors) -> std::io::Result<()> {
+    let mut sidecar_cursors: Vec<_> = cursors
+        .iter()
+        .filter_map(|(&hash, cursor)| {
+            Some(SidecarCursor {
+                id: cursor.id,
+                hash,
+                file_name: cursor.file_name.clone()?,
+                hotspot: cursor.hotspot,
+                hotspot_px: cursor.hotspot_px,
+                resolution: cursor.resolution,
+                screen_size: cursor.screen_size,
+                scale: cursor.scale,
+                kind: cursor.kind,
+                system_name: cursor.system_name.clone(),
+                animation: cursor.animation.as_ref().map(|animation| SidecarAnimation {
+                    frame_files: animation.frame_files.clone(),
+                    frame_duration_ms: animation.frame_duration_ms,
+                }),
+            })
+        })
+        .collect();
+    sidecar_cursors.sort_by_key(|cursor| cursor.id);
+
+    let json = serde_json::to_vec_pretty(&Sidecar {
+        hash_version: CURSOR_HASH_VERSION,
+        cursors: sidecar_cursors,
+    })?;
+
+    let path = cursors_dir.join(CURSOR_SIDECAR_FILE);
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, json)?;
+    std::fs::rename(&temp, &path)
+}
+
+/// Rebuilds the [`Cursors`] saved to `cursors_dir` by an earlier recording, eg. to pass
+/// to [`super::CursorRecorderConfig::prev_cursors`] when picking a project back up after
+/// a restart. Any `next_cursor_id` works alongside them, as the recorder continues after
+/// the highest id it was given.
+///
+/// Every `cursor_{id}` image in the directory is loaded, with its hotspot, size and kind
+/// restored from the [`CURSOR_SIDECAR_FILE`]. Cursors are keyed by the hash the sidecar
+/// recorded when it's of the current [`CURSOR_HASH_VERSION`], and otherwise by hashing
+/// the file again, which still matches newly captured images unless they were resized
+/// or saved as WebP. Images the sidecar doesn't list, such as ones saved just before a
+/// crash, are loaded with their hotspot at the top left corner, and sidecar entries
+/// whose image is gone are left out. A missing directory has no cursors.
+pub fn load_cursors_from_dir(cursors_dir: &Path) -> std::io::Result<Cursors> {
+    let sidecar = match std::fs::read(cursors_dir.join(CURSOR_SIDECAR_FILE)) {
+        Ok(json) => Some(serde_json::from_slice::<Sidecar>(&json)?),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    let trust_hashes = sidecar
+        .as_ref()
+        .is_some_and(|sidecar| sidecar.hash_version == CURSOR_HASH_VERSION);
+    let mut listed: HashMap<String, SidecarCursor> = sidecar
+        .into_iter()
+        .flat_map(|sidecar| sidecar.cursors)
+        .map(|cursor| (cursor.file_name.clone(), cursor))
+        .collect();
+
+    let entries = match std::fs::read_dir(cursors_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Cursors::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut cursors = Cursors::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Some((file_name, id)) = first_frame_id(&path) else {
+            continue;
+        };
+
+        let image = std::fs::read(&path)?;
+
+        let (hash, cursor) = match listed.remove(&file_name) {
+            Some(listed) => (
+                if trust_hashes {
+                    listed.hash
+                } else {
+                    cursor_image_hash(&image)
+                },
+                Cursor {
+                    file_name: Some(file_name),
+                    id: listed.id,
+                    hotspot: listed.hotspot,
+                    hotspot_px: listed.hotspot_px,
+                    kind: listed.kind,
+                    system_name: listed.system_name,
+                    scale: listed.scale,
+                    resolution: listed.resolution,
+                    screen_size: listed.screen_size,
+                    animation: listed.animation.map(|animation| CursorAnimation {
+                        frame_files: animation.frame_files,
+                        frame_duration_ms: animation.frame_duration_ms,
+                    }),
+                    possibly_stale: false,
+                },
+            ),
+            None => {
+                warn!(
+                    "Cursor image {file_name} isn't in the sidecar, loading it without its hotspot"
+                );
+                let resolution = image_dimensions(&image)
+                    .map_or(XY::new(0, 0), |(width, height)| XY::new(width, height));
+                (
+                    cursor_image_hash(&image),
+                    Cursor {
+                        file_name: Some(file_name),
+                        id,
+                        hotspot: XY::new(0.0, 0.0),
+                        hotspot_px: XY::new(0, 0),
+                        kind: CursorKind::Custom,
+                        system_name: None,
+                        scale: 1.0,
+                        resolution,
+                        screen_size: XY::new(0.0, 0.0),
+                        animation: None,
+                        possibly_stale: false,
+                    },
+                )
+            }
+        };
+
+        cursors.insert(hash, cursor);
+    }
+
+    Ok(cursors)
+}
+
+/// The file name and id of `cursor_{id}.{ext}` images, skipping animation frames, which
+/// are named `cursor_{id}_{frame}.{ext}`.
+fn first_frame_id(path: &Path) -> Option<(String, u32)> {
+    let file_name = path.file_name()?.to_str()?;
+    if !super::is_cursor_file(file_name) {
+        return None;
+    }
+
+    let (stem, _) = file_name.rsplit_once('.')?;
+    let id = stem.strip_prefix("cursor_")?.parse().ok()?;
+    Some((file_name.to_string(), id))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cursor(id: u32, hotspot: XY<f64>) -> Cursor {
+        Cursor {
+            file_name: Some(format!("cursor_{id}.png")),
+            id,
+            hotspot,
+            hotspot_px: XY::new(2, 2),
+            kind: CursorKind::IBeam,
+            system_name: Some("IBeam".to_string()),
+            scale: 2.0,
+            resolution: XY::new(8, 8),
+            screen_size: XY::new(0.04, 0.04),
+            animation: None,
+            possibly_stale: false,
+        }
+    }
+
+    #[test]
+    fn loads_cursors_from_the_sidecar() {
+        let dir = std::env::temp_dir().join("cap-cursor-sidecar");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::write(dir.join("cursor_0.png"), b"first").unwrap();
+        std::fs::write(dir.join("cursor_1.png"), b"second").unwrap();
+        // Neither an animation frame nor a file that isn't a cursor is a cursor of its own
+        std::fs::write(dir.join("cursor_1_1.png"), b"frame").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"").unwrap();
+
+        let mut saved = Cursors::new();
+        saved.insert(10, cursor(0, XY::new(0.25, 0.25)));
+        saved.insert(11, cursor(1, XY::new(0.5, 0.5)));
+        // Its image was deleted since
+        saved.insert(12, cursor(2, XY::new(0.0, 0.0)));
+        write_cursor_sidecar(&dir, &saved).unwrap();
+
+        std::fs::write(dir.join("cursor_3.png"), b"unlisted").unwrap();
+
+        let loaded = load_cursors_from_dir(&dir).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[&10].hotspot, XY::new(0.25, 0.25));
+        assert_eq!(loaded[&11].kind, CursorKind::IBeam);
+        assert_eq!(loaded[&11].scale, 2.0);
+
+        let unlisted = &loaded[&cursor_image_hash(b"unlisted")];
+        assert_eq!(unlisted.id, 3);
+        assert_eq!(unlisted.hotspot, XY::new(0.0, 0.0));
+
+        assert!(load_cursors_from_dir(&dir.join("missing"))
+            .unwrap()
+            .is_empty());
+    }
+}
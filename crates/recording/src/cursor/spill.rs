@@ -0,0 +1,144 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use cap_project::CursorEvents;
+use tracing::{debug, error};
+
+use super::CursorActorResponse;
+
+/// Moves buffered events out to an append-only file once too many have piled up, and
+/// merges them back in front of the in-memory events when recording stops.
+///
+/// Each spill is one line of JSON holding a [`CursorEvents`] chunk. Writing a chunk
+/// briefly blocks the polling loop, which is the price for keeping memory bounded.
+pub struct EventSpill {
+    path: PathBuf,
+    max_buffered: usize,
+    file: Option<BufWriter<File>>,
+    failed: bool,
+}
+
+impl EventSpill {
+    pub fn new(path: PathBuf, max_buffered: usize) -> Self {
+        Self {
+            path,
+            max_buffered,
+            file: None,
+            failed: false,
+        }
+    }
+
+    pub fn spill_if_full(&mut self, response: &mut CursorActorResponse) {
+        let buffered = response.moves.len()
+            + response.clicks.len()
+            + response.scrolls.len()
+            + response.drags.len()
+            + response.modifier_events.len()
+            + response.busy_events.len();
+        if self.failed || buffered == 0 || buffered < self.max_buffered {
+            return;
+        }
+
+        let chunk = CursorEvents {
+            moves: std::mem::take(&mut response.moves),
+            clicks: std::mem::take(&mut response.clicks),
+            scrolls: std::mem::take(&mut response.scrolls),
+            drags: std::mem::take(&mut response.drags),
+            modifier_events: std::mem::take(&mut response.modifier_events),
+            busy_events: std::mem::take(&mut response.busy_events),
+        };
+
+        if let Err(e) = self.write_chunk(&chunk) {
+            // Keep everything in memory from here on rather than lose events
+            error!("Failed to spill cursor events to {:?}: {e}", self.path);
+            self.failed = true;
+
+            response.moves = chunk.moves;
+            response.clicks = chunk.clicks;
+            response.scrolls = chunk.scrolls;
+            response.drags = chunk.drags;
+            response.modifier_events = chunk.modifier_events;
+            response.busy_events = chunk.busy_events;
+            return;
+        }
+
+        debug!("Spilled {buffered} cursor events to {:?}", self.path);
+    }
+
+    fn write_chunk(&mut self, chunk: &CursorEvents) -> std::io::Result<()> {
+        let line = serde_json::to_string(chunk)?;
+
+        let file = match &mut self.file {
+            Some(file) => file,
+            file => file.insert(BufWriter::new(File::create(&self.path)?)),
+        };
+        writeln!(file, "{line}")?;
+        file.flush()
+    }
+
+    /// Deletes the spill file without reading it back.
+    pub fn discard(self) {
+        let Some(file) = self.file else {
+            return;
+        };
+        drop(file);
+
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            error!("Failed to remove cursor spill file {:?}: {e}", self.path);
+        }
+    }
+
+    /// Puts the spilled events back in front of the ones still in memory and deletes
+    /// the spill file.
+    pub fn restore(self, response: &mut CursorActorResponse) {
+        let Some(file) = self.file else {
+            return;
+        };
+        drop(file);
+
+        let mut events = CursorEvents::default();
+        let res = File::open(&self.path).and_then(|file| {
+            for line in BufReader::new(file).lines() {
+                let chunk: CursorEvents = serde_json::from_str(&line?)?;
+                events.moves.extend(chunk.moves);
+                events.clicks.extend(chunk.clicks);
+                events.scrolls.extend(chunk.scrolls);
+                events.drags.extend(chunk.drags);
+                events.modifier_events.extend(chunk.modifier_events);
+                events.busy_events.extend(chunk.busy_events);
+            }
+
+            Ok(())
+        });
+
+        match res {
+            Ok(()) => {
+                if let Err(e) = std::fs::remove_file(&self.path) {
+                    error!("Failed to remove cursor spill file {:?}: {e}", self.path);
+                }
+            }
+            // The file is left in place so the events can still be recovered by hand
+            Err(e) => error!(
+                "Failed to restore spilled cursor events from {:?}: {e}",
+                self.path
+            ),
+        }
+
+        events.moves.append(&mut response.moves);
+        events.clicks.append(&mut response.clicks);
+        events.scrolls.append(&mut response.scrolls);
+        events.drags.append(&mut response.drags);
+        events.modifier_events.append(&mut response.modifier_events);
+        events.busy_events.append(&mut response.busy_events);
+
+        response.moves = events.moves;
+        response.clicks = events.clicks;
+        response.scrolls = events.scrolls;
+        response.drags = events.drags;
+        response.modifier_events = events.modifier_events;
+        response.busy_events = events.busy_events;
+    }
+}
@@ -0,0 +1,37 @@
+//! Platforms without a native backend record every event with the default cursor
+//! and never report scrolls.
+
+use super::CursorRecordError;
+
+#[cfg(feature = "cursor-images")]
+use super::CursorData;
+
+#[derive(PartialEq)]
+pub enum CursorHandle {}
+
+pub fn current_cursor_handle() -> Option<CursorHandle> {
+    None
+}
+
+pub fn position_scale() -> f64 {
+    1.0
+}
+
+pub fn input_access() -> Result<(), CursorRecordError> {
+    Ok(())
+}
+
+pub fn cursor_possibly_stale() -> bool {
+    false
+}
+
+pub fn cursor_visible() -> bool {
+    true
+}
+
+#[cfg(feature = "cursor-images")]
+pub fn get_cursor_image_data() -> Option<CursorData> {
+    None
+}
+
+pub fn spawn_input_listener() {}
@@ -0,0 +1,116 @@
+//! Wayland deliberately hides the pointer from other clients: there's no protocol for
+//! reading the global cursor position or the image the compositor is drawing, and
+//! cursor-shape only goes the other way, letting a client set its own cursor. So under
+//! a Wayland session, compared to X11:
+//!
+//! - Cursor images aren't recorded, and every event uses the default cursor. XWayland's
+//!   cursor is ignored too, as it goes stale whenever a native window has the pointer.
+//! - Positions come from `device_query` through XWayland, so they only update while the
+//!   cursor is over an XWayland window.
+//! - Scrolls are only seen over XWayland windows, and only with the `x11` feature.
+//! - Visibility can't be queried, so the cursor is always reported as visible.
+//!
+//! Outside a Wayland session everything is handed to the X11 backend when the `x11`
+//! feature is enabled, so a single build works on both.
+
+use std::sync::OnceLock;
+
+use tracing::warn;
+
+use super::CursorRecordError;
+
+#[cfg(feature = "cursor-images")]
+use super::CursorData;
+
+#[cfg(feature = "x11")]
+use super::x11_backend;
+
+#[cfg(feature = "x11")]
+pub use x11_backend::CursorHandle;
+
+#[cfg(not(feature = "x11"))]
+#[derive(PartialEq)]
+pub enum CursorHandle {}
+
+fn on_wayland() -> bool {
+    static ON_WAYLAND: OnceLock<bool> = OnceLock::new();
+
+    *ON_WAYLAND.get_or_init(|| {
+        std::env::var_os("WAYLAND_DISPLAY").is_some()
+            || std::env::var_os("XDG_SESSION_TYPE").is_some_and(|session| session == "wayland")
+    })
+}
+
+pub fn current_cursor_handle() -> Option<CursorHandle> {
+    #[cfg(feature = "x11")]
+    if !on_wayland() {
+        return x11_backend::current_cursor_handle();
+    }
+
+    None
+}
+
+pub fn position_scale() -> f64 {
+    #[cfg(feature = "x11")]
+    if !on_wayland() {
+        return x11_backend::position_scale();
+    }
+
+    1.0
+}
+
+/// Positions are read through XWayland even in a Wayland session.
+pub fn input_access() -> Result<(), CursorRecordError> {
+    #[cfg(feature = "x11")]
+    return x11_backend::input_access();
+
+    #[cfg(not(feature = "x11"))]
+    Ok(())
+}
+
+pub fn cursor_possibly_stale() -> bool {
+    #[cfg(feature = "x11")]
+    if !on_wayland() {
+        return x11_backend::cursor_possibly_stale();
+    }
+
+    false
+}
+
+pub fn cursor_visible() -> bool {
+    #[cfg(feature = "x11")]
+    if !on_wayland() {
+        return x11_backend::cursor_visible();
+    }
+
+    true
+}
+
+#[cfg(feature = "cursor-images")]
+pub fn get_cursor_image_data() -> Option<CursorData> {
+    #[cfg(feature = "x11")]
+    if !on_wayland() {
+        return x11_backend::get_cursor_image_data();
+    }
+
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        if on_wayland() {
+            warn!("The Wayland compositor doesn't expose the cursor image, cursors will not be recorded");
+        } else {
+            warn!("Not running under Wayland and built without X11 support, cursors will not be recorded");
+        }
+    });
+
+    None
+}
+
+pub fn spawn_input_listener() {
+    if on_wayland() {
+        warn!("Scrolls are only recorded over XWayland windows under Wayland");
+    }
+
+    // XInput still sees XWayland windows, which beats recording no scrolls at all
+    #[cfg(feature = "x11")]
+    x11_backend::spawn_input_listener();
+}
@@ -0,0 +1,937 @@
+use tracing::warn;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::UI::WindowsAndMessaging::{CURSORINFO, HCURSOR};
+
+use super::{
+    accumulate_raw_motion, accumulate_scroll, enable_raw_motion, set_pointer_device,
+    set_press_position, CursorRecordError,
+};
+
+#[cfg(feature = "cursor-images")]
+use std::sync::{Mutex, PoisonError};
+
+#[cfg(feature = "cursor-images")]
+use cap_project::XY;
+#[cfg(feature = "cursor-images")]
+use image::RgbaImage;
+#[cfg(feature = "cursor-images")]
+use windows::Win32::Graphics::Gdi::{BITMAPINFO, BITMAPINFOHEADER, HBITMAP, HDC, HGDIOBJ};
+#[cfg(feature = "cursor-images")]
+use windows::Win32::UI::WindowsAndMessaging::ICONINFO;
+
+#[cfg(feature = "cursor-images")]
+use super::{image_dimensions, images::encode_png, CursorData, CursorKind};
+
+/// `HCURSOR` value of the current cursor. Shared system cursors keep the same handle for
+/// as long as they're loaded, so an unchanged handle means an unchanged image.
+#[derive(PartialEq)]
+pub struct CursorHandle(usize);
+
+pub fn current_cursor_handle() -> Option<CursorHandle> {
+    current_cursor().map(|cursor| CursorHandle(cursor.0 as usize))
+}
+
+#[cfg(feature = "cursor-images")]
+pub fn get_cursor_image_data() -> Option<CursorData> {
+    unsafe { cursor_image_data(current_cursor()?) }
+}
+
+/// Captures the current cursor `size` pixels square. `LR_COPYFROMRESOURCE` reloads the
+/// cursor from its resource, picking the image closest to `size` instead of stretching
+/// the one on screen, though cursors without a larger image are still stretched.
+#[cfg(feature = "cursor-images")]
+pub fn get_cursor_image_data_at(size: u32) -> Option<CursorData> {
+    unsafe { resized_cursor_image_data(current_cursor()?, size) }
+}
+
+#[cfg(feature = "cursor-images")]
+unsafe fn resized_cursor_image_data(cursor: HCURSOR, size: u32) -> Option<CursorData> {
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CopyImage, DestroyCursor, IMAGE_CURSOR, LR_COPYFROMRESOURCE,
+    };
+
+    let on_screen = cursor_image_data(cursor)?;
+
+    // Copies only keep the first frame of animated cursors
+    if !on_screen.frames.is_empty() {
+        return Some(on_screen);
+    }
+
+    let Ok(copy) = CopyImage(
+        HANDLE(cursor.0),
+        IMAGE_CURSOR,
+        size as i32,
+        size as i32,
+        LR_COPYFROMRESOURCE,
+    ) else {
+        return Some(on_screen);
+    };
+    let copy = HCURSOR(copy.0);
+    let resized = cursor_image_data(copy);
+    let _ = DestroyCursor(copy);
+
+    let (Some(resized), Some((on_screen_width, _))) = (resized, image_dimensions(&on_screen.image))
+    else {
+        return Some(on_screen);
+    };
+    let (width, _) = image_dimensions(&resized.image)?;
+
+    Some(CursorData {
+        // The copy is a different handle, so it wouldn't match the system cursors
+        kind: on_screen.kind,
+        scale: on_screen.scale * width as f64 / on_screen_width as f64,
+        ..resized
+    })
+}
+
+/// Cap is DPI aware, so mouse positions are in physical pixels rather than points.
+pub fn position_scale() -> f64 {
+    system_scale()
+}
+
+/// Any desktop app can read the cursor and install a low level mouse hook.
+pub fn input_access() -> Result<(), CursorRecordError> {
+    Ok(())
+}
+
+/// `GetCursorInfo` reports the cursor on screen, whichever process set it.
+pub fn cursor_possibly_stale() -> bool {
+    false
+}
+
+pub fn cursor_visible() -> bool {
+    use windows::Win32::UI::WindowsAndMessaging::CURSOR_SHOWING;
+
+    // Assume the cursor is shown if Windows won't say, like the other platforms do
+    cursor_info().is_none_or(|cursor_info| cursor_info.flags.0 & CURSOR_SHOWING.0 != 0)
+}
+
+fn current_cursor() -> Option<HCURSOR> {
+    let cursor_info = cursor_info()?;
+
+    // If no cursor, return None
+    if cursor_info.hCursor.is_invalid() {
+        return None;
+    }
+
+    Some(cursor_info.hCursor)
+}
+
+fn cursor_info() -> Option<CURSORINFO> {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::UI::WindowsAndMessaging::{GetCursorInfo, CURSORINFO_FLAGS};
+
+    unsafe {
+        // Get cursor info
+        let mut cursor_info = CURSORINFO {
+            cbSize: std::mem::size_of::<CURSORINFO>() as u32,
+            flags: CURSORINFO_FLAGS(0),
+            hCursor: Default::default(),
+            ptScreenPos: POINT::default(),
+        };
+
+        // Handle Result return type
+        if GetCursorInfo(&mut cursor_info).is_err() {
+            return None;
+        }
+
+        Some(cursor_info)
+    }
+}
+
+/// `GetIconInfo` hands ownership of both bitmaps to the caller, so they're deleted
+/// however the capture ends.
+#[cfg(feature = "cursor-images")]
+struct IconInfo(ICONINFO);
+
+#[cfg(feature = "cursor-images")]
+impl IconInfo {
+    unsafe fn new(cursor: HCURSOR) -> Option<Self> {
+        use windows::Win32::UI::WindowsAndMessaging::GetIconInfo;
+
+        let mut icon_info = ICONINFO::default();
+        GetIconInfo(cursor, &mut icon_info).ok()?;
+        Some(Self(icon_info))
+    }
+}
+
+#[cfg(feature = "cursor-images")]
+impl Drop for IconInfo {
+    fn drop(&mut self) {
+        use windows::Win32::Graphics::Gdi::DeleteObject;
+
+        unsafe {
+            if !self.0.hbmColor.is_invalid() {
+                let _ = DeleteObject(self.0.hbmColor);
+            }
+            let _ = DeleteObject(self.0.hbmMask);
+        }
+    }
+}
+
+#[cfg(feature = "cursor-images")]
+unsafe fn cursor_image_data(cursor: HCURSOR) -> Option<CursorData> {
+    let icon_info = IconInfo::new(cursor)?;
+    let icon_info = &icon_info.0;
+
+    // Monochrome cursors (eg. the classic I-beam) only have a mask bitmap
+    let (image, (frames, frame_duration_ms)) = if icon_info.hbmColor.is_invalid() {
+        (monochrome_cursor_image(icon_info), (vec![], 0.0))
+    } else {
+        (
+            color_cursor_image(cursor, icon_info, 0),
+            animation_frames(cursor, icon_info),
+        )
+    };
+
+    let image = image?;
+
+    Some(CursorData {
+        image: encode_png(&image)?,
+        hotspot: XY::new(
+            icon_info.xHotspot as f64 / image.width() as f64,
+            icon_info.yHotspot as f64 / image.height() as f64,
+        ),
+        kind: cursor_kind(cursor),
+        system_name: None,
+        scale: system_scale(),
+        frames: frames.iter().filter_map(encode_png).collect(),
+        frame_duration_ms,
+    })
+}
+
+// Undocumented, but exported by user32 since XP and the only way to read the frame count
+// and rate of an animated cursor
+#[cfg(feature = "cursor-images")]
+#[link(name = "user32")]
+extern "system" {
+    fn GetCursorFrameInfo(
+        cursor: HCURSOR,
+        reserved: u32,
+        step: u32,
+        rate_jiffies: *mut u32,
+        num_steps: *mut u32,
+    ) -> HCURSOR;
+}
+
+/// Guards against corrupt cursors claiming an absurd number of frames.
+#[cfg(feature = "cursor-images")]
+const MAX_ANIMATION_FRAMES: u32 = 128;
+
+/// Renders every frame after the first of an animated cursor, along with how long each
+/// frame is shown. Cursors with per-frame rates are approximated by the first rate.
+#[cfg(feature = "cursor-images")]
+unsafe fn animation_frames(cursor: HCURSOR, icon_info: &ICONINFO) -> (Vec<RgbaImage>, f64) {
+    let (mut rate_jiffies, mut num_steps) = (0, 0);
+    GetCursorFrameInfo(cursor, 0, 0, &mut rate_jiffies, &mut num_steps);
+    if num_steps <= 1 {
+        return (vec![], 0.0);
+    }
+
+    let frames = (1..num_steps.min(MAX_ANIMATION_FRAMES))
+        .map_while(|step| color_cursor_image(cursor, icon_info, step))
+        .collect();
+
+    // A jiffy is 1/60th of a second
+    (frames, rate_jiffies as f64 * 1000.0 / 60.0)
+}
+
+/// Cursors are drawn at the system DPI, so their bitmaps grow with the display scale.
+fn system_scale() -> f64 {
+    use windows::Win32::Graphics::Gdi::{GetDC, GetDeviceCaps, ReleaseDC, LOGPIXELSX};
+
+    unsafe {
+        let screen_dc = GetDC(None);
+        let dpi = GetDeviceCaps(screen_dc, LOGPIXELSX);
+        ReleaseDC(None, screen_dc);
+
+        if dpi > 0 {
+            dpi as f64 / 96.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// System cursors are shared, so loading one by its `IDC_*` id hands back the same
+/// handle the cursor info reports.
+#[cfg(feature = "cursor-images")]
+fn cursor_kind(cursor: HCURSOR) -> CursorKind {
+    use windows::Win32::UI::WindowsAndMessaging::*;
+
+    const KINDS: [(windows::core::PCWSTR, CursorKind); 14] = [
+        (IDC_ARROW, CursorKind::Arrow),
+        (IDC_IBEAM, CursorKind::IBeam),
+        (IDC_HAND, CursorKind::PointingHand),
+        (IDC_CROSS, CursorKind::Crosshair),
+        (IDC_NO, CursorKind::NotAllowed),
+        (IDC_WAIT, CursorKind::Wait),
+        (IDC_APPSTARTING, CursorKind::Wait),
+        (IDC_SIZEWE, CursorKind::ResizeLeftRight),
+        (IDC_SIZENS, CursorKind::ResizeUpDown),
+        (IDC_SIZENWSE, CursorKind::ResizeNwse),
+        (IDC_SIZENESW, CursorKind::ResizeNesw),
+        (IDC_SIZEALL, CursorKind::Move),
+        (IDC_UPARROW, CursorKind::Arrow),
+        (IDC_HELP, CursorKind::Arrow),
+    ];
+
+    KINDS
+        .iter()
+        .find(|(id, _)| unsafe { LoadCursorW(None, *id) }.is_ok_and(|system| system == cursor))
+        .map_or(CursorKind::Custom, |(_, kind)| *kind)
+}
+
+/// GDI objects every capture needs, kept from one to the next: a memory DC to draw and
+/// read masks through, and the DIB section color cursors are drawn onto, which is only
+/// recreated when a cursor of another size comes along. Most cursors share one size, so
+/// capturing a new cursor or frame usually allocates no GDI objects at all.
+#[cfg(feature = "cursor-images")]
+struct CaptureDc {
+    dc: HDC,
+    // What the DC had selected before the first canvas, put back before deleting it
+    original: Option<HGDIOBJ>,
+    canvas: Option<Canvas>,
+}
+
+/// A top-down 32bpp DIB section, selected into the [`CaptureDc`].
+#[cfg(feature = "cursor-images")]
+struct Canvas {
+    dib: HBITMAP,
+    bits: *mut u8,
+    width: i32,
+    height: i32,
+}
+
+// GDI objects can be used from any thread, and these only ever are with the lock held
+#[cfg(feature = "cursor-images")]
+unsafe impl Send for CaptureDc {}
+
+#[cfg(feature = "cursor-images")]
+impl CaptureDc {
+    unsafe fn new() -> Option<Self> {
+        use windows::Win32::Graphics::Gdi::{CreateCompatibleDC, GetDC, ReleaseDC};
+
+        let screen_dc = GetDC(HWND::default());
+        let dc = CreateCompatibleDC(screen_dc);
+        ReleaseDC(HWND::default(), screen_dc);
+        if dc.is_invalid() {
+            return None;
+        }
+
+        Some(Self {
+            dc,
+            original: None,
+            canvas: None,
+        })
+    }
+
+    /// The canvas, cleared and exactly `width` by `height`, as both `DrawIconEx` and the
+    /// copy out assume.
+    unsafe fn canvas(&mut self, width: i32, height: i32) -> Option<&Canvas> {
+        use windows::Win32::Graphics::Gdi::{
+            CreateDIBSection, DeleteObject, SelectObject, DIB_RGB_COLORS,
+        };
+
+        let reusable = self
+            .canvas
+            .as_ref()
+            .is_some_and(|canvas| canvas.width == width && canvas.height == height);
+        if !reusable {
+            let mut bits: *mut std::ffi::c_void = std::ptr::null_mut();
+            let dib = CreateDIBSection(
+                self.dc,
+                &top_down_bitmap_info(width, height),
+                DIB_RGB_COLORS,
+                &mut bits,
+                None,
+                0,
+            )
+            .ok()?;
+
+            // Selecting the new canvas frees the old one to be deleted
+            let previous = SelectObject(self.dc, dib);
+            self.original.get_or_insert(previous);
+            if let Some(old) = self.canvas.take() {
+                let _ = DeleteObject(old.dib);
+            }
+
+            self.canvas = Some(Canvas {
+                dib,
+                bits: bits as *mut u8,
+                width,
+                height,
+            });
+        }
+
+        let canvas = self.canvas.as_ref()?;
+        std::ptr::write_bytes(canvas.bits, 0, (width * height * 4) as usize);
+        Some(canvas)
+    }
+}
+
+#[cfg(feature = "cursor-images")]
+impl Drop for CaptureDc {
+    fn drop(&mut self) {
+        use windows::Win32::Graphics::Gdi::{DeleteDC, DeleteObject, SelectObject};
+
+        unsafe {
+            if let Some(original) = self.original {
+                SelectObject(self.dc, original);
+            }
+            if let Some(canvas) = self.canvas.take() {
+                let _ = DeleteObject(canvas.dib);
+            }
+            let _ = DeleteDC(self.dc);
+        }
+    }
+}
+
+#[cfg(feature = "cursor-images")]
+static CAPTURE_DC: Mutex<Option<CaptureDc>> = Mutex::new(None);
+
+/// Runs `f` with the shared [`CaptureDc`], creating it first if the last one was released.
+#[cfg(feature = "cursor-images")]
+unsafe fn with_capture_dc<T>(f: impl FnOnce(&mut CaptureDc) -> Option<T>) -> Option<T> {
+    let mut capture_dc = CAPTURE_DC.lock().unwrap_or_else(PoisonError::into_inner);
+    let capture_dc = match &mut *capture_dc {
+        Some(capture_dc) => capture_dc,
+        capture_dc => capture_dc.insert(CaptureDc::new()?),
+    };
+
+    f(capture_dc)
+}
+
+/// Deletes the GDI objects kept between captures. Another recorder that's still running
+/// just creates them again on its next capture.
+#[cfg(feature = "cursor-images")]
+pub fn release_capture_resources() {
+    drop(
+        CAPTURE_DC
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take(),
+    );
+}
+
+/// `step` picks the frame of an animated cursor, and is ignored by static ones.
+#[cfg(feature = "cursor-images")]
+unsafe fn color_cursor_image(
+    cursor: HCURSOR,
+    icon_info: &ICONINFO,
+    step: u32,
+) -> Option<RgbaImage> {
+    use windows::Win32::Graphics::Gdi::{GetObjectA, BITMAP};
+    use windows::Win32::UI::WindowsAndMessaging::{DrawIconEx, DI_NORMAL, HICON};
+
+    // Get bitmap info
+    let mut bitmap = BITMAP::default();
+    if GetObjectA(
+        icon_info.hbmColor,
+        std::mem::size_of::<BITMAP>() as i32,
+        Some(&mut bitmap as *mut _ as *mut _),
+    ) == 0
+    {
+        return None;
+    }
+    if bitmap.bmWidth <= 0 || bitmap.bmHeight <= 0 {
+        return None;
+    }
+
+    let mut image_data = with_capture_dc(|capture_dc| {
+        let dc = capture_dc.dc;
+        let canvas = capture_dc.canvas(bitmap.bmWidth, bitmap.bmHeight)?;
+
+        // Draw the cursor itself onto a transparent background, rather than copying
+        // whatever is on screen underneath it
+        DrawIconEx(
+            dc,
+            0,
+            0,
+            HICON(cursor.0),
+            bitmap.bmWidth,
+            bitmap.bmHeight,
+            step,
+            None,
+            DI_NORMAL,
+        )
+        .ok()?;
+
+        let size = (bitmap.bmWidth * bitmap.bmHeight * 4) as usize;
+        Some(std::slice::from_raw_parts(canvas.bits, size).to_vec())
+    })?;
+
+    premultiplied_bgra_to_rgba(&mut image_data);
+
+    RgbaImage::from_raw(bitmap.bmWidth as u32, bitmap.bmHeight as u32, image_data)
+}
+
+/// DIBs are stored as BGRA, and `DrawIconEx` blends the cursor onto the transparent DIB,
+/// which leaves its colors premultiplied by alpha. `RgbaImage` is straight alpha, so
+/// without undoing that, soft edges come out with dark fringes once composited.
+#[cfg(feature = "cursor-images")]
+fn premultiplied_bgra_to_rgba(pixels: &mut [u8]) {
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+
+        let alpha = pixel[3] as u32;
+        for channel in &mut pixel[..3] {
+            *channel = match alpha {
+                0 => 0,
+                alpha => ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8,
+            };
+        }
+    }
+}
+
+/// Monochrome cursor masks stack the AND mask on top of the XOR mask, so the bitmap
+/// is twice as tall as the cursor. Each pixel is decoded as:
+///
+/// | AND | XOR | Result             |
+/// |-----|-----|--------------------|
+/// | 0   | 0   | Black              |
+/// | 0   | 1   | White              |
+/// | 1   | 0   | Transparent        |
+/// | 1   | 1   | Inverted (→ black) |
+///
+/// Inverting the screen can't be represented in a standalone image, so those
+/// pixels are drawn black, which is how they appear on light backgrounds.
+#[cfg(feature = "cursor-images")]
+unsafe fn monochrome_cursor_image(icon_info: &ICONINFO) -> Option<RgbaImage> {
+    use windows::Win32::Graphics::Gdi::{GetDIBits, GetObjectA, BITMAP, DIB_RGB_COLORS};
+
+    let mut bitmap = BITMAP::default();
+    if GetObjectA(
+        icon_info.hbmMask,
+        std::mem::size_of::<BITMAP>() as i32,
+        Some(&mut bitmap as *mut _ as *mut _),
+    ) == 0
+    {
+        return None;
+    }
+
+    let width = bitmap.bmWidth;
+    let height = bitmap.bmHeight / 2;
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+
+    // Have GDI expand the 1bpp mask to 32bpp so each pixel is either 0 or 0xFFFFFF
+    let mut bitmap_info = top_down_bitmap_info(width, bitmap.bmHeight);
+    let mut mask = vec![0u32; (width * bitmap.bmHeight) as usize];
+
+    // The mask isn't selected into any DC, so it can be read through the shared one
+    let lines = with_capture_dc(|capture_dc| {
+        Some(GetDIBits(
+            capture_dc.dc,
+            icon_info.hbmMask,
+            0,
+            bitmap.bmHeight as u32,
+            Some(mask.as_mut_ptr() as *mut _),
+            &mut bitmap_info,
+            DIB_RGB_COLORS,
+        ))
+    })?;
+    if lines != bitmap.bmHeight {
+        return None;
+    }
+
+    let (and_mask, xor_mask) = mask.split_at((width * height) as usize);
+
+    let mut image_data = Vec::with_capacity(and_mask.len() * 4);
+    for (&and, &xor) in and_mask.iter().zip(xor_mask) {
+        image_data.extend_from_slice(match (and & 0xFFFFFF != 0, xor & 0xFFFFFF != 0) {
+            (false, false) => &[0, 0, 0, 255],
+            (false, true) => &[255, 255, 255, 255],
+            (true, false) => &[0, 0, 0, 0],
+            (true, true) => &[0, 0, 0, 255],
+        });
+    }
+
+    RgbaImage::from_raw(width as u32, height as u32, image_data)
+}
+
+#[cfg(feature = "cursor-images")]
+fn top_down_bitmap_info(width: i32, height: i32) -> BITMAPINFO {
+    BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            biHeight: -height, // Negative height for top-down bitmap
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: 0,
+            biSizeImage: 0,
+            biXPelsPerMeter: 0,
+            biYPelsPerMeter: 0,
+            biClrUsed: 0,
+            biClrImportant: 0,
+        },
+        bmiColors: [Default::default()],
+    }
+}
+
+pub fn spawn_input_listener() {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        DispatchMessageW, GetMessageW, SetWindowsHookExW, UnhookWindowsHookEx, MSG, WH_MOUSE_LL,
+    };
+
+    std::thread::spawn(|| unsafe {
+        // Low-level hooks are called on the installing thread, which needs a message loop
+        let hook = match SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook), None, 0) {
+            Ok(hook) => hook,
+            Err(e) => {
+                warn!("Failed to install mouse hook, scrolls and pointer devices will not be recorded: {e}");
+                return;
+            }
+        };
+
+        // The hook only sees where the cursor ended up, which stops changing once an app
+        // captures it, while raw input keeps reporting how the mouse moved
+        if let Err(e) = register_raw_mouse_input() {
+            warn!("Failed to register for raw mouse input, motion deltas will be computed from positions: {e}");
+        }
+
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+            DispatchMessageW(&msg);
+        }
+
+        UnhookWindowsHookEx(hook).ok();
+    });
+}
+
+unsafe extern "system" fn mouse_hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, MSLLHOOKSTRUCT, WHEEL_DELTA, WM_LBUTTONDOWN, WM_MBUTTONDOWN,
+        WM_MOUSEHWHEEL, WM_MOUSEWHEEL, WM_RBUTTONDOWN, WM_XBUTTONDOWN,
+    };
+
+    if code >= 0 {
+        let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+        set_pointer_device(pointer_device(info.dwExtraInfo));
+
+        // The high word holds the signed wheel delta, in multiples of WHEEL_DELTA per notch
+        let delta = (info.mouseData >> 16) as i16 as f64 / WHEEL_DELTA as f64;
+
+        let coords = (info.pt.x, info.pt.y);
+        match wparam.0 as u32 {
+            WM_MOUSEWHEEL => accumulate_scroll(0.0, delta),
+            WM_MOUSEHWHEEL => accumulate_scroll(delta, 0.0),
+            WM_LBUTTONDOWN => set_press_position(1, coords),
+            WM_RBUTTONDOWN => set_press_position(2, coords),
+            WM_MBUTTONDOWN => set_press_position(3, coords),
+            // The high word is 1 for the back button and 2 for forward
+            WM_XBUTTONDOWN => set_press_position(3 + (info.mouseData >> 16) as u8, coords),
+            _ => {}
+        }
+    }
+
+    CallNextHookEx(None, code, wparam, lparam)
+}
+
+const RAW_INPUT_CLASS: windows::core::PCWSTR = windows::core::w!("CapCursorRawInput");
+
+/// Raw input is only delivered to windows, so this creates a message-only one on the
+/// calling thread and has it receive mouse input even while other apps have focus.
+unsafe fn register_raw_mouse_input() -> windows::core::Result<()> {
+    use windows::core::PCWSTR;
+    use windows::Win32::UI::Input::{RegisterRawInputDevices, RAWINPUTDEVICE, RIDEV_INPUTSINK};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, RegisterClassW, HWND_MESSAGE, WINDOW_EX_STYLE, WINDOW_STYLE, WNDCLASSW,
+    };
+
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(raw_input_window),
+        lpszClassName: RAW_INPUT_CLASS,
+        ..Default::default()
+    };
+    if RegisterClassW(&class) == 0 {
+        return Err(windows::core::Error::from_win32());
+    }
+
+    let window = CreateWindowExW(
+        WINDOW_EX_STYLE::default(),
+        RAW_INPUT_CLASS,
+        PCWSTR::null(),
+        WINDOW_STYLE::default(),
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE,
+        None,
+        None,
+        None,
+    )?;
+
+    // The generic desktop page's mouse usage
+    let device = RAWINPUTDEVICE {
+        usUsagePage: 0x01,
+        usUsage: 0x02,
+        dwFlags: RIDEV_INPUTSINK,
+        hwndTarget: window,
+    };
+    RegisterRawInputDevices(&[device], std::mem::size_of::<RAWINPUTDEVICE>() as u32)?;
+    enable_raw_motion();
+
+    Ok(())
+}
+
+unsafe extern "system" fn raw_input_window(
+    window: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    use windows::Win32::UI::Input::{
+        GetRawInputData, HRAWINPUT, MOUSE_MOVE_ABSOLUTE, RAWINPUT, RAWINPUTHEADER, RID_INPUT,
+        RIM_TYPEMOUSE,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{DefWindowProcW, WM_INPUT};
+
+    if msg == WM_INPUT {
+        // Only mice are registered, whose input always fits
+        let mut input = RAWINPUT::default();
+        let mut size = std::mem::size_of::<RAWINPUT>() as u32;
+        let read = GetRawInputData(
+            HRAWINPUT(lparam.0 as _),
+            RID_INPUT,
+            Some(&mut input as *mut RAWINPUT as *mut _),
+            &mut size,
+            std::mem::size_of::<RAWINPUTHEADER>() as u32,
+        );
+
+        // Tablets and remote desktop sessions send absolute positions through here
+        // too, which aren't motion
+        if read != u32::MAX && input.header.dwType == RIM_TYPEMOUSE.0 {
+            let mouse = input.data.mouse;
+            if mouse.usFlags.0 & MOUSE_MOVE_ABSOLUTE.0 == 0 {
+                accumulate_raw_motion(mouse.lLastX as f64, mouse.lLastY as f64);
+            }
+        }
+    }
+
+    // Lets the system free the input
+    DefWindowProcW(window, msg, wparam, lparam)
+}
+
+const PEN_DEVICE_ID: u32 = 1;
+const TOUCH_DEVICE_ID: u32 = 2;
+
+/// Windows merges every mouse and touchpad into one pointer, but tags the mouse messages
+/// it synthesizes for pens and touch, so those two get ids of their own.
+fn pointer_device(extra_info: usize) -> u32 {
+    // MI_WP_SIGNATURE from the pen input docs, which sets 0x80 for touch rather than pen
+    const MI_WP_SIGNATURE: usize = 0xFF515700;
+    const SIGNATURE_MASK: usize = 0xFFFFFF00;
+
+    if extra_info & SIGNATURE_MASK != MI_WP_SIGNATURE {
+        0
+    } else if extra_info & 0x80 != 0 {
+        TOUCH_DEVICE_ID
+    } else {
+        PEN_DEVICE_ID
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "cursor-images")]
+    use windows::Win32::UI::WindowsAndMessaging::{LoadCursorW, IDC_ARROW};
+
+    use super::*;
+
+    #[cfg(feature = "cursor-images")]
+    #[test]
+    fn renders_system_arrow() {
+        let cursor = unsafe { LoadCursorW(None, IDC_ARROW) }.unwrap();
+
+        let data = unsafe { cursor_image_data(cursor) }.unwrap();
+        let image = image::load_from_memory(&data.image).unwrap().into_rgba8();
+
+        // The arrow has a transparent background around an opaque glyph
+        assert!(image.pixels().any(|p| p[3] == 0));
+        assert!(image.pixels().any(|p| p[3] == 255));
+
+        // Rendering the same cursor again must produce identical bytes, otherwise
+        // every poll would look like a new cursor
+        let again = unsafe { cursor_image_data(cursor) }.unwrap();
+        assert_eq!(data.image, again.image);
+
+        // The arrow's tip is its hotspot
+        assert!(data.hotspot.x < 0.25 && data.hotspot.y < 0.25);
+
+        assert_eq!(data.kind, CursorKind::Arrow);
+        assert!(data.scale >= 1.0);
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[test]
+    fn resizes_system_arrow() {
+        let cursor = unsafe { LoadCursorW(None, IDC_ARROW) }.unwrap();
+
+        let on_screen = unsafe { cursor_image_data(cursor) }.unwrap();
+        let resized = unsafe { resized_cursor_image_data(cursor, 128) }.unwrap();
+        let image = image::load_from_memory(&resized.image).unwrap();
+
+        assert_eq!((image.width(), image.height()), (128, 128));
+        assert_eq!(resized.kind, CursorKind::Arrow);
+        assert!(resized.scale > on_screen.scale);
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[test]
+    fn reuses_canvas_of_the_same_size() {
+        let mut capture_dc = unsafe { CaptureDc::new() }.unwrap();
+
+        let first = unsafe { capture_dc.canvas(32, 32) }.unwrap().dib;
+        let again = unsafe { capture_dc.canvas(32, 32) }.unwrap().dib;
+        assert!(first == again);
+
+        let larger = unsafe { capture_dc.canvas(48, 48) }.unwrap();
+        assert!((larger.width, larger.height) == (48, 48));
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[test]
+    fn failed_captures_release_gdi_objects() {
+        use windows::Win32::Foundation::HANDLE;
+
+        #[link(name = "user32")]
+        extern "system" {
+            fn GetGuiResources(process: HANDLE, flags: u32) -> u32;
+        }
+        // The pseudo handle `GetCurrentProcess` returns, and GR_GDIOBJECTS
+        let gdi_objects = || unsafe { GetGuiResources(HANDLE(-1isize as _), 0) };
+
+        let arrow = unsafe { LoadCursorW(None, IDC_ARROW) }.unwrap();
+        let icon_info = unsafe { IconInfo::new(arrow) }.unwrap();
+        let not_a_cursor = HCURSOR(0x1234 as _);
+
+        // The shared capture DC stays alive across captures, so it's created up front
+        unsafe { cursor_image_data(arrow) }.unwrap();
+        let before = gdi_objects();
+
+        for _ in 0..2000 {
+            // Fails straight away in GetIconInfo
+            assert!(unsafe { cursor_image_data(not_a_cursor) }.is_none());
+            // Fails drawing, after the canvas and bitmap info were set up
+            assert!(unsafe { color_cursor_image(not_a_cursor, &icon_info.0, 0) }.is_none());
+            // Succeeds, with the same bitmaps taken and freed every time
+            unsafe { cursor_image_data(arrow) }.unwrap();
+        }
+
+        // Other tests may be capturing at the same time, but nowhere near this many
+        let after = gdi_objects();
+        assert!(after < before + 100, "{before} GDI objects grew to {after}");
+    }
+
+    #[test]
+    fn devices_from_extra_info() {
+        assert_eq!(pointer_device(0), 0);
+        assert_eq!(pointer_device(0xFF515700), PEN_DEVICE_ID);
+        assert_eq!(pointer_device(0xFF515780), TOUCH_DEVICE_ID);
+        // Other apps put their own values here when injecting input
+        assert_eq!(pointer_device(0x1234), 0);
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[test]
+    fn unpremultiplies_colors() {
+        let mut pixels = [
+            // Half transparent mid gray, stored as BGRA
+            64, 64, 64, 128, //
+            // Quarter opaque red
+            0, 0, 64, 64, //
+            255, 255, 255, 255, //
+            9, 9, 9, 0,
+        ];
+        premultiplied_bgra_to_rgba(&mut pixels);
+
+        assert_eq!(
+            pixels,
+            [128, 128, 128, 128, 255, 0, 0, 64, 255, 255, 255, 255, 0, 0, 0, 0]
+        );
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[test]
+    fn keeps_soft_edges_light() {
+        use windows::Win32::Graphics::Gdi::{CreateBitmap, DeleteObject};
+        use windows::Win32::UI::WindowsAndMessaging::{CreateIconIndirect, DestroyIcon};
+
+        // 32x32 white cursor that fades out towards the right, with premultiplied colors
+        // like cursor files store them
+        let mut pixels = Vec::with_capacity(32 * 32 * 4);
+        for _ in 0..32 {
+            for x in 0..32u32 {
+                let alpha = (255 - x * 8) as u8;
+                pixels.extend_from_slice(&[alpha, alpha, alpha, alpha]);
+            }
+        }
+        let mask = [0u8; 32 * 32 / 8];
+
+        let icon = unsafe {
+            let color = CreateBitmap(32, 32, 1, 32, Some(pixels.as_ptr() as *const _));
+            let mask = CreateBitmap(32, 32, 1, 1, Some(mask.as_ptr() as *const _));
+            let icon = CreateIconIndirect(&ICONINFO {
+                fIcon: false.into(),
+                xHotspot: 0,
+                yHotspot: 0,
+                hbmMask: mask,
+                hbmColor: color,
+            });
+            let _ = DeleteObject(color);
+            let _ = DeleteObject(mask);
+            icon.unwrap()
+        };
+
+        let data = unsafe { cursor_image_data(HCURSOR(icon.0)) }.unwrap();
+        unsafe { DestroyIcon(icon) }.unwrap();
+
+        let image = image::load_from_memory(&data.image).unwrap().into_rgba8();
+        assert_eq!(image.get_pixel(0, 0).0[3], 255);
+        for x in [16, 30] {
+            let pixel = image.get_pixel(x, 0).0;
+            assert!(pixel[3] < 255, "{pixel:?} at {x}");
+            // Still white, rather than darkening along with the alpha
+            assert!(pixel[..3].iter().all(|&c| c >= 250), "{pixel:?} at {x}");
+        }
+    }
+
+    #[cfg(feature = "cursor-images")]
+    #[test]
+    fn renders_monochrome_cursor() {
+        use windows::Win32::UI::WindowsAndMessaging::{CreateCursor, DestroyCursor};
+
+        // 32x32 cursor with an opaque black left half and transparent right half
+        let row_and = [0x00u8, 0x00, 0xFF, 0xFF];
+        let row_xor = [0x00u8; 4];
+        let and_plane = row_and.repeat(32);
+        let xor_plane = row_xor.repeat(32);
+
+        let cursor = unsafe {
+            CreateCursor(
+                None,
+                16,
+                8,
+                32,
+                32,
+                and_plane.as_ptr() as *const _,
+                xor_plane.as_ptr() as *const _,
+            )
+        }
+        .unwrap();
+
+        let data = unsafe { cursor_image_data(cursor) }.unwrap();
+        unsafe { DestroyCursor(cursor) }.unwrap();
+
+        let image = image::load_from_memory(&data.image).unwrap().into_rgba8();
+        assert_eq!(image.dimensions(), (32, 32));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(31, 31).0[3], 0);
+        assert_eq!(data.hotspot, XY::new(0.5, 0.25));
+    }
+}
@@ -0,0 +1,324 @@
+use std::ptr;
+
+use tracing::warn;
+use x11::{xinput2, xlib};
+
+use super::{accumulate_scroll, set_pointer_device, CursorRecordError};
+
+#[cfg(feature = "cursor-images")]
+use cap_project::XY;
+#[cfg(feature = "cursor-images")]
+use x11::xfixes;
+
+#[cfg(feature = "cursor-images")]
+use super::{images::encode_png, CursorData, CursorKind};
+
+struct Display(*mut xlib::Display);
+
+impl Drop for Display {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XCloseDisplay(self.0);
+        }
+    }
+}
+
+#[cfg(feature = "cursor-images")]
+thread_local! {
+    // Xlib connections aren't thread safe, so each worker thread gets its own
+    static DISPLAY: Option<Display> = open_display();
+}
+
+#[cfg(feature = "cursor-images")]
+fn open_display() -> Option<Display> {
+    unsafe {
+        let display = xlib::XOpenDisplay(ptr::null());
+        if display.is_null() {
+            warn!("Failed to open X display, cursor images will not be recorded");
+            return None;
+        }
+
+        let display = Display(display);
+
+        let (mut event_base, mut error_base) = (0, 0);
+        if xfixes::XFixesQueryExtension(display.0, &mut event_base, &mut error_base) == 0 {
+            warn!("XFixes extension is unavailable, cursor images will not be recorded");
+            return None;
+        }
+
+        Some(display)
+    }
+}
+
+/// The serial XFixes gives each cursor image. It only comes alongside the full image,
+/// so the image is still fetched on every tick, but converting, encoding and hashing it
+/// is skipped while the serial stays the same.
+#[cfg(feature = "cursor-images")]
+#[derive(PartialEq)]
+pub struct CursorHandle(u64);
+
+/// Handles only serve to skip capturing unchanged images, which are never captured
+/// without the `cursor-images` feature.
+#[cfg(not(feature = "cursor-images"))]
+#[derive(PartialEq)]
+pub enum CursorHandle {}
+
+#[cfg(not(feature = "cursor-images"))]
+pub fn current_cursor_handle() -> Option<CursorHandle> {
+    None
+}
+
+#[cfg(feature = "cursor-images")]
+pub fn current_cursor_handle() -> Option<CursorHandle> {
+    DISPLAY.with(|display| {
+        let display = display.as_ref()?;
+
+        unsafe {
+            let cursor_image = xfixes::XFixesGetCursorImage(display.0);
+            if cursor_image.is_null() {
+                return None;
+            }
+
+            let serial = (*cursor_image).cursor_serial;
+            xlib::XFree(cursor_image as *mut _);
+
+            Some(CursorHandle(serial as u64))
+        }
+    })
+}
+
+/// X11 has no logical points, positions and cursor images are both in pixels.
+pub fn position_scale() -> f64 {
+    1.0
+}
+
+/// X11 has no permissions, but positions are read through a display connection, which
+/// fails without a running server or with `DISPLAY` unset.
+pub fn input_access() -> Result<(), CursorRecordError> {
+    let display = unsafe { xlib::XOpenDisplay(ptr::null()) };
+    if display.is_null() {
+        return Err(CursorRecordError::InputUnavailable(
+            "can't connect to the X display",
+        ));
+    }
+    drop(Display(display));
+
+    Ok(())
+}
+
+/// XFixes always reports the cursor the server is drawing.
+pub fn cursor_possibly_stale() -> bool {
+    false
+}
+
+/// Core X11 and XFixes can hide the cursor but offer no way to ask whether it's hidden.
+pub fn cursor_visible() -> bool {
+    true
+}
+
+#[cfg(feature = "cursor-images")]
+pub fn get_cursor_image_data() -> Option<CursorData> {
+    DISPLAY.with(|display| {
+        let display = display.as_ref()?;
+
+        unsafe {
+            let cursor_image = xfixes::XFixesGetCursorImage(display.0);
+            if cursor_image.is_null() {
+                return None;
+            }
+
+            let data = cursor_image_to_data(&*cursor_image);
+            xlib::XFree(cursor_image as *mut _);
+
+            data
+        }
+    })
+}
+
+#[cfg(feature = "cursor-images")]
+unsafe fn cursor_image_to_data(cursor_image: &xfixes::XFixesCursorImage) -> Option<CursorData> {
+    let width = cursor_image.width as u32;
+    let height = cursor_image.height as u32;
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    // XFixes hands out one premultiplied ARGB pixel per c_ulong, which is 64 bits wide on
+    // most platforms, so the pixels can't be reinterpreted as a byte slice directly
+    let pixels = std::slice::from_raw_parts(cursor_image.pixels, (width * height) as usize);
+
+    let mut rgba = Vec::with_capacity(pixels.len() * 4);
+    for &pixel in pixels {
+        let pixel = pixel as u32;
+        let a = (pixel >> 24) as u8;
+        let unpremultiply = |c: u32| {
+            if a == 0 {
+                0
+            } else {
+                ((c & 0xff) * 255 / a as u32).min(255) as u8
+            }
+        };
+
+        rgba.extend_from_slice(&[
+            unpremultiply(pixel >> 16),
+            unpremultiply(pixel >> 8),
+            unpremultiply(pixel),
+            a,
+        ]);
+    }
+
+    let image = image::RgbaImage::from_raw(width, height, rgba)?;
+
+    Some(CursorData {
+        image: encode_png(&image)?,
+        hotspot: XY::new(
+            cursor_image.xhot as f64 / width as f64,
+            cursor_image.yhot as f64 / height as f64,
+        ),
+        kind: if cursor_image.name.is_null() {
+            CursorKind::Custom
+        } else {
+            cursor_kind(&std::ffi::CStr::from_ptr(cursor_image.name).to_string_lossy())
+        },
+        system_name: None,
+        // Themes pick their cursor size from Xcursor.size rather than a scale factor, so
+        // the image is taken at face value
+        scale: 1.0,
+        // Animated cursors show up as a new image on each frame change instead
+        frames: vec![],
+        frame_duration_ms: 0.0,
+    })
+}
+
+/// Maps cursor theme names, which are a mix of legacy X cursor font names and their CSS
+/// equivalents depending on the toolkit, to a kind.
+#[cfg(feature = "cursor-images")]
+fn cursor_kind(name: &str) -> CursorKind {
+    match name {
+        "left_ptr" | "default" | "arrow" | "top_left_arrow" => CursorKind::Arrow,
+        "xterm" | "text" | "ibeam" | "vertical-text" => CursorKind::IBeam,
+        "hand2" | "pointer" | "pointing_hand" => CursorKind::PointingHand,
+        "hand1" | "openhand" | "grab" => CursorKind::OpenHand,
+        "closedhand" | "grabbing" | "dnd-move" => CursorKind::ClosedHand,
+        "crosshair" | "cross" | "tcross" => CursorKind::Crosshair,
+        "not-allowed" | "crossed_circle" | "no-drop" | "forbidden" => CursorKind::NotAllowed,
+        "watch" | "wait" | "progress" | "left_ptr_watch" => CursorKind::Wait,
+        "sb_h_double_arrow" | "h_double_arrow" | "ew-resize" | "col-resize" | "e-resize"
+        | "w-resize" | "left_side" | "right_side" | "size_hor" => CursorKind::ResizeLeftRight,
+        "sb_v_double_arrow" | "v_double_arrow" | "ns-resize" | "row-resize" | "n-resize"
+        | "s-resize" | "top_side" | "bottom_side" | "size_ver" => CursorKind::ResizeUpDown,
+        "bd_double_arrow"
+        | "nwse-resize"
+        | "nw-resize"
+        | "se-resize"
+        | "top_left_corner"
+        | "bottom_right_corner"
+        | "size_fdiag" => CursorKind::ResizeNwse,
+        "fd_double_arrow" | "nesw-resize" | "ne-resize" | "sw-resize" | "top_right_corner"
+        | "bottom_left_corner" | "size_bdiag" => CursorKind::ResizeNesw,
+        "fleur" | "move" | "all-scroll" | "size_all" => CursorKind::Move,
+        _ => CursorKind::Custom,
+    }
+}
+
+pub fn spawn_input_listener() {
+    std::thread::spawn(|| unsafe {
+        // XNextEvent blocks, so the listener gets a connection of its own
+        let display = xlib::XOpenDisplay(ptr::null());
+        if display.is_null() {
+            warn!("Failed to open X display, scrolls and pointer devices will not be recorded");
+            return;
+        }
+        let display = Display(display);
+
+        let (mut opcode, mut event_base, mut error_base) = (0, 0, 0);
+        if xlib::XQueryExtension(
+            display.0,
+            c"XInputExtension".as_ptr(),
+            &mut opcode,
+            &mut event_base,
+            &mut error_base,
+        ) == 0
+        {
+            warn!(
+                "XInput extension is unavailable, scrolls and pointer devices will not be recorded"
+            );
+            return;
+        }
+
+        // Raw events only name the physical device that sent them from XInput 2.1 on
+        let (mut major, mut minor) = (2, 2);
+        if xinput2::XIQueryVersion(display.0, &mut major, &mut minor) != xlib::Success as i32 {
+            warn!("XInput 2 is unavailable, scrolls and pointer devices will not be recorded");
+            return;
+        }
+
+        // Raw events are delivered to the root window regardless of which client has focus
+        let mut mask = [0u8; 4];
+        xinput2::XISetMask(&mut mask, xinput2::XI_RawButtonPress);
+        xinput2::XISetMask(&mut mask, xinput2::XI_RawMotion);
+        let mut event_mask = xinput2::XIEventMask {
+            deviceid: xinput2::XIAllMasterDevices,
+            mask_len: mask.len() as i32,
+            mask: mask.as_mut_ptr(),
+        };
+        xinput2::XISelectEvents(
+            display.0,
+            xlib::XDefaultRootWindow(display.0),
+            &mut event_mask,
+            1,
+        );
+        xlib::XFlush(display.0);
+
+        loop {
+            let mut event: xlib::XEvent = std::mem::zeroed();
+            xlib::XNextEvent(display.0, &mut event);
+
+            let cookie = &mut event.generic_event_cookie;
+            if cookie.type_ != xlib::GenericEvent
+                || cookie.extension != opcode
+                || xlib::XGetEventData(display.0, cookie) == 0
+            {
+                continue;
+            }
+
+            let raw_event = &*(cookie.data as *const xinput2::XIRawEvent);
+
+            // Ids 0 and 1 stand for all devices, so older servers that leave the source
+            // unset keep reporting the merged pointer
+            if raw_event.sourceid > 1 {
+                set_pointer_device(raw_event.sourceid as u32);
+            }
+
+            if cookie.evtype == xinput2::XI_RawButtonPress {
+                // X11 reports each wheel notch as a press of buttons 4-7
+                match raw_event.detail {
+                    4 => accumulate_scroll(0.0, 1.0),
+                    5 => accumulate_scroll(0.0, -1.0),
+                    6 => accumulate_scroll(-1.0, 0.0),
+                    7 => accumulate_scroll(1.0, 0.0),
+                    _ => {}
+                }
+            }
+
+            xlib::XFreeEventData(display.0, cookie);
+        }
+    });
+}
+
+#[cfg(all(test, feature = "cursor-images"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn kinds_from_theme_names() {
+        assert_eq!(cursor_kind("left_ptr"), CursorKind::Arrow);
+        assert_eq!(cursor_kind("text"), CursorKind::IBeam);
+        assert_eq!(cursor_kind("hand2"), CursorKind::PointingHand);
+        assert_eq!(
+            cursor_kind("sb_h_double_arrow"),
+            CursorKind::ResizeLeftRight
+        );
+        assert_eq!(cursor_kind("my-app-cursor"), CursorKind::Custom);
+    }
+}
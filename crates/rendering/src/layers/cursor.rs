@@ -85,6 +85,9 @@ impl CursorLayer {
             (speed * 0.3).min(1.0) * uniforms.project.motion_blur.unwrap_or(0.8);
 
         let cursor_event = find_cursor_event(&cursor, segment_time);
+        if !cursor_event.visible || cursor_event.off_screen {
+            return;
+        }
 
         let last_click_time = cursor
             .clicks